@@ -0,0 +1,122 @@
+use super::polygon::{PolygonData, Vertex};
+use super::tree::{build_bsp_node, BSPNode};
+
+/// Builds the solid covering either input, following the csg.js formulation
+/// of Naylor, Amanatides and Thibault: each tree's surface is clipped to the
+/// other, with an extra inverted pass that drops one copy of faces the two
+/// solids share, and the surviving polygons are rebuilt into a new tree.
+/// Both inputs must be closed solids with outward-facing polygons.
+pub fn union<V: Vertex, T: PolygonData>(a: BSPNode<V, T>, b: BSPNode<V, T>) -> BSPNode<V, T> {
+    let mut a = a;
+    let mut b = b;
+
+    a.clip_to(&b);
+    b.clip_to(&a);
+    b.invert();
+    b.clip_to(&a);
+    b.invert();
+
+    let mut polygons = a.all_polygons();
+    polygons.append(&mut b.all_polygons());
+
+    return build_bsp_node(polygons);
+}
+
+/// Builds the solid common to both inputs. Like `union`, it expects closed
+/// solids whose polygons face outward and whose trees treat back leaves as
+/// solid; the work is done on inverted trees, so the result is inverted back
+/// before it is returned.
+pub fn intersect<V: Vertex, T: PolygonData>(a: BSPNode<V, T>, b: BSPNode<V, T>) -> BSPNode<V, T> {
+    let mut a = a;
+    let mut b = b;
+
+    a.invert();
+    b.clip_to(&a);
+    b.invert();
+    a.clip_to(&b);
+    b.clip_to(&a);
+
+    let mut polygons = a.all_polygons();
+    polygons.append(&mut b.all_polygons());
+
+    let mut result = build_bsp_node(polygons);
+    result.invert();
+
+    return result;
+}
+
+/// Removes the solid of `b` from `a`, with the same input conventions as
+/// `union`.
+pub fn subtract<V: Vertex, T: PolygonData>(a: BSPNode<V, T>, b: BSPNode<V, T>) -> BSPNode<V, T> {
+    let mut a = a;
+    let mut b = b;
+
+    a.invert();
+    a.clip_to(&b);
+    b.clip_to(&a);
+    b.invert();
+    b.clip_to(&a);
+    b.invert();
+
+    let mut polygons = a.all_polygons();
+    polygons.append(&mut b.all_polygons());
+
+    let mut result = build_bsp_node(polygons);
+    result.invert();
+
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::math::{Scalar, Vec3};
+    use super::super::shapes::bsp_cube_faces;
+    use super::super::tree::from_polygons;
+
+    fn cube(center: Vec3, half: Scalar) -> BSPNode {
+        return from_polygons(bsp_cube_faces(center, Vec3::ONE * half)).unwrap();
+    }
+
+    #[test]
+    fn union_of_overlapping_cubes() {
+        let result = union(cube(Vec3::ZERO, 1.), cube(Vec3::ONE, 1.));
+
+        assert!(result.point_in_solid(Vec3 { x: -0.5, y: -0.5, z: -0.5 }));
+        assert!(result.point_in_solid(Vec3 { x: 1.5, y: 1.5, z: 1.5 }));
+        assert!(result.point_in_solid(Vec3 { x: 0.5, y: 0.5, z: 0.5 }));
+        assert!(!result.point_in_solid(Vec3 { x: 1.5, y: -0.5, z: 0. }));
+        assert!((result.surface_area() - 42.).abs() < 1e-3);
+        assert!((result.volume() - 15.).abs() < 1e-3);
+        assert_eq!(result.stats().polygon_count, 24);
+    }
+
+    #[test]
+    fn intersection_of_offset_cubes_is_the_overlap() {
+        let result = intersect(cube(Vec3::ZERO, 1.), cube(Vec3::ONE, 1.));
+
+        for corner in 0..8 {
+            let bit = |b: usize| if corner & b != 0 { 1. } else { 0. };
+            let c = Vec3 { x: bit(1), y: bit(2), z: bit(4) };
+            let inward = (Vec3::ONE * 0.5 - c) * 0.1;
+
+            assert!(result.point_in_solid(c + inward));
+            assert!(!result.point_in_solid(c - inward));
+        }
+
+        assert!((result.volume() - 1.).abs() < 1e-3);
+        assert_eq!(result.bounds(), Some((Vec3::ZERO, Vec3::ONE)));
+    }
+
+    #[test]
+    fn subtracting_a_small_cube_carves_a_hole() {
+        let result = subtract(cube(Vec3::ZERO, 2.), cube(Vec3::ZERO, 1.));
+
+        assert!(!result.point_in_solid(Vec3::ZERO));
+        assert!(!result.point_in_solid(Vec3 { x: 0.9, y: -0.9, z: 0.5 }));
+        assert!(result.point_in_solid(Vec3 { x: 1.5, y: 0., z: 0. }));
+        assert!(result.point_in_solid(Vec3 { x: -1.5, y: 1.5, z: -1.5 }));
+        assert!(!result.point_in_solid(Vec3 { x: 2.5, y: 0., z: 0. }));
+        assert!((result.volume() - 56.).abs() < 1e-3);
+    }
+}
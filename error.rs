@@ -0,0 +1,29 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Everything that can go wrong turning outside data into polygons or trees.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BspError {
+    /// A polygon has fewer than three vertices, encloses no area, or has
+    /// coordinates that are not finite.
+    DegeneratePolygon,
+    /// A polygon's vertices do not all lie on one plane.
+    NonPlanarPolygon,
+    /// Input data could not be read. The message says where and why.
+    ParseError(String),
+    /// There was nothing to build from.
+    EmptyInput,
+}
+
+impl fmt::Display for BspError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            BspError::DegeneratePolygon => write!(f, "polygon encloses no area"),
+            BspError::NonPlanarPolygon => write!(f, "polygon vertices are not coplanar"),
+            BspError::ParseError(message) => write!(f, "parse error: {}", message),
+            BspError::EmptyInput => write!(f, "no input geometry"),
+        };
+    }
+}
+
+impl core::error::Error for BspError {}
@@ -0,0 +1,43 @@
+//! The whole pipeline at `f32` precision.
+
+/// The scalar type and the tolerances tuned to it.
+pub mod scalar {
+    pub type Scalar = f32;
+    /// An unsigned integer as wide as `Scalar`, for bit-pattern keys.
+    pub type ScalarBits = u32;
+
+    pub use core::f32::consts::PI;
+
+    pub(crate) const NORMALIZE_EPS: Scalar = 1e-6;
+
+    /// How far from a plane a point may lie and still count as on it, for
+    /// queries that take no `BuildConfig`.
+    pub const PLANE_THICKNESS_EPS: Scalar = 1e-4;
+}
+
+#[path = "csg.rs"]
+pub mod csg;
+#[cfg(feature = "std")]
+#[path = "io.rs"]
+pub mod io;
+#[path = "math.rs"]
+pub mod math;
+#[path = "plane.rs"]
+pub mod plane;
+#[path = "polygon.rs"]
+pub mod polygon;
+#[path = "shapes.rs"]
+pub mod shapes;
+#[path = "tree.rs"]
+pub mod tree;
+
+/// The types and functions most programs need, for a single glob import.
+pub mod prelude {
+    pub use crate::error::BspError;
+    pub use super::csg::{intersect, subtract, union};
+    pub use super::math::{Mat4, Scalar, Vec3};
+    pub use super::plane::{classify_point_to_plane, BSPPlane, PointPlaneSide};
+    pub use super::polygon::{bsp_polygon_by_vertices, classify_polygon_by_plane, BSPPolygon, PolygonData, PolygonPlaneSide, Vertex};
+    pub use super::shapes::{bsp_cone_faces, bsp_cube_faces, bsp_cylinder_faces, bsp_sphere_faces};
+    pub use super::tree::{build_bsp_node_with_config, from_polygons, BSPNode, BSPTreeBuilder, BuildConfig, Cell};
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use crate::error::BspError;
+use super::math::{Scalar, Vec3};
+use super::polygon::{bsp_polygon_by_vertices, BSPPolygon};
+
+/// Builds one polygon per face from a shared vertex array and 0-based index
+/// lists. An index past the end of `vertices` is a `ParseError`.
+pub fn from_indexed(vertices: &[Vec3], faces: &[Vec<usize>]) -> Result<Vec<BSPPolygon>, BspError> {
+    if faces.is_empty() {
+        return Err(BspError::EmptyInput);
+    }
+
+    let mut polygons: Vec<BSPPolygon> = Vec::with_capacity(faces.len());
+
+    for (face, indices) in faces.iter().enumerate() {
+        let mut face_vertices: Vec<Vec3> = Vec::with_capacity(indices.len());
+
+        for &index in indices.iter() {
+            match vertices.get(index) {
+                Some(v) => face_vertices.push(*v),
+                None => return Err(BspError::ParseError(format!("face {}: no vertex {}", face, index))),
+            }
+        }
+
+        polygons.push(bsp_polygon_by_vertices(face_vertices)?);
+    }
+
+    return Ok(polygons);
+}
+
+/// Reads the `v` and `f` lines of a Wavefront OBJ file into polygons, one per
+/// face. Face indices may be 1-based or negative, counting back from the
+/// latest vertex, and any texture or normal indices after a `/` are ignored,
+/// as are all other kinds of line. A file without faces is `EmptyInput`.
+pub fn from_obj_str(src: &str) -> Result<Vec<BSPPolygon>, BspError> {
+    let mut vertices: Vec<Vec3> = Vec::new();
+    let mut polygons: Vec<BSPPolygon> = Vec::new();
+
+    for (i, raw) in src.lines().enumerate() {
+        let line = i + 1;
+        let content = raw.split('#').next().unwrap_or("");
+        let mut tokens = content.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => {
+                let coords: Vec<Scalar> = tokens
+                    .take(3)
+                    .map(|token| token.parse::<Scalar>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| BspError::ParseError(format!("line {}: {}", line, e)))?;
+
+                if coords.len() < 3 {
+                    return Err(BspError::ParseError(format!("line {}: vertex needs three coordinates", line)));
+                }
+
+                vertices.push(Vec3 { x: coords[0], y: coords[1], z: coords[2] });
+            }
+            Some("f") => {
+                let mut face: Vec<Vec3> = Vec::new();
+
+                for token in tokens {
+                    let index: i64 = token.split('/').next().unwrap_or("").parse()
+                        .map_err(|_| BspError::ParseError(format!("line {}: bad face index {:?}", line, token)))?;
+
+                    let resolved = if index < 0 { vertices.len() as i64 + index } else { index - 1 };
+
+                    match usize::try_from(resolved).ok().and_then(|j| vertices.get(j)) {
+                        Some(v) => face.push(*v),
+                        None => return Err(BspError::ParseError(format!("line {}: no vertex {}", line, index))),
+                    }
+                }
+
+                if face.len() < 3 {
+                    return Err(BspError::ParseError(format!("line {}: face needs three vertices", line)));
+                }
+
+                polygons.push(bsp_polygon_by_vertices(face)?);
+            }
+            _ => {}
+        }
+    }
+
+    if polygons.is_empty() {
+        return Err(BspError::EmptyInput);
+    }
+
+    return Ok(polygons);
+}
+
+/// Writes the polygons as a Wavefront OBJ mesh. Vertices with identical bits
+/// are written once and shared by every face that uses them.
+pub fn to_obj_str(polygons: &[BSPPolygon]) -> String {
+    let mut indices: HashMap<Vec3, usize> = HashMap::new();
+    let mut vertex_lines = String::new();
+    let mut face_lines = String::new();
+
+    for polygon in polygons.iter() {
+        face_lines.push('f');
+
+        for v in polygon.vertices.iter() {
+            let next = indices.len() + 1;
+            let index = *indices.entry(*v).or_insert_with(|| {
+                vertex_lines.push_str(&format!("v {} {} {}\n", v.x, v.y, v.z));
+                return next;
+            });
+
+            face_lines.push_str(&format!(" {}", index));
+        }
+
+        face_lines.push('\n');
+    }
+
+    return vertex_lines + &face_lines;
+}
+
+const STL_HEADER_LEN: usize = 80;
+const STL_TRIANGLE_LEN: usize = 50;
+
+/// Reads a binary STL file into one triangle per facet. The stored facet
+/// normals are ignored in favour of the winding, and facets that enclose no
+/// area are dropped. Pass the result through `merge_coplanar_polygons` to
+/// get larger faces back. A file whose size does not match its triangle
+/// count is a `ParseError`.
+pub fn from_stl_bytes(bytes: &[u8]) -> Result<Vec<BSPPolygon>, BspError> {
+    if bytes.len() < STL_HEADER_LEN + 4 {
+        return Err(BspError::ParseError(format!("expected at least {} bytes, found {}", STL_HEADER_LEN + 4, bytes.len())));
+    }
+
+    let count_bytes: [u8; 4] = bytes[STL_HEADER_LEN..STL_HEADER_LEN + 4].try_into().unwrap();
+    let count = u32::from_le_bytes(count_bytes) as usize;
+    let expected = STL_HEADER_LEN + 4 + count * STL_TRIANGLE_LEN;
+
+    if bytes.len() != expected {
+        return Err(BspError::ParseError(format!("expected {} bytes, found {}", expected, bytes.len())));
+    }
+
+    let read_f32 = |offset: usize| {
+        return f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as Scalar;
+    };
+
+    let mut polygons: Vec<BSPPolygon> = Vec::new();
+
+    for i in 0..count {
+        // Skip the 12-byte facet normal.
+        let start = STL_HEADER_LEN + 4 + i * STL_TRIANGLE_LEN + 12;
+
+        let vertices: Vec<Vec3> = (0..3).map(|k| {
+            let offset = start + k * 12;
+            return Vec3 { x: read_f32(offset), y: read_f32(offset + 4), z: read_f32(offset + 8) };
+        }).collect();
+
+        if let Ok(polygon) = bsp_polygon_by_vertices(vertices) {
+            polygons.push(polygon);
+        }
+    }
+
+    return Ok(polygons);
+}
+
+/// Writes the polygons as a binary STL file, splitting each into a fan of
+/// triangles since STL holds nothing else. Coordinates are stored as `f32`.
+pub fn to_stl_bytes(polygons: &[BSPPolygon]) -> Vec<u8> {
+    let mut header = [0u8; STL_HEADER_LEN];
+    let title = b"binary STL written by rust-bsp";
+    header[..title.len()].copy_from_slice(title);
+
+    let mut body: Vec<u8> = Vec::new();
+    let mut count: u32 = 0;
+
+    for polygon in polygons.iter() {
+        for triangle in polygon.triangulate() {
+            push_stl_vec3(&mut body, polygon.plane.n);
+
+            for v in triangle {
+                push_stl_vec3(&mut body, v);
+            }
+
+            body.extend_from_slice(&0u16.to_le_bytes());
+
+            count += 1;
+        }
+    }
+
+    let mut bytes = header.to_vec();
+    bytes.extend_from_slice(&count.to_le_bytes());
+    bytes.append(&mut body);
+
+    return bytes;
+}
+
+// The cast is a no-op at `f32` precision.
+#[allow(clippy::unnecessary_cast)]
+fn push_stl_vec3(body: &mut Vec<u8>, v: Vec3) {
+    for c in [v.x, v.y, v.z] {
+        body.extend_from_slice(&(c as f32).to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::shapes::bsp_cube_faces;
+    use super::super::polygon::merge_coplanar_polygons;
+    use super::super::tree::from_polygons;
+
+    const CUBE_OBJ: &str = "\
+    # unit cube
+    v -1 -1 -1
+    v 1 -1 -1
+    v -1 1 -1
+    v 1 1 -1
+    v -1 -1 1
+    v 1 -1 1
+    v -1 1 1
+    v 1 1 1
+    f 1 5 7 3
+    f 2 4 8 6
+    f 1 2 6 5
+    f 3 7 8 4
+    f 1 3 4 2
+    f 5/1 6/2 8/3 7/4
+    ";
+
+    #[test]
+    fn import_a_cube() {
+        let polygons = from_obj_str(CUBE_OBJ).unwrap();
+
+        assert_eq!(polygons.len(), 6);
+        assert!(polygons.iter().all(|p| p.vertices.len() == 4 && p.plane.n.dot(p.centroid()) > 0.));
+        assert!(matches!(from_obj_str("v 0 0 0\nf 1 2 3\n"), Err(BspError::ParseError(_))));
+        assert_eq!(from_obj_str("v 0 0 0\n").err(), Some(BspError::EmptyInput));
+    }
+
+    #[test]
+    fn obj_round_trip() {
+        let faces = bsp_cube_faces(Vec3 { x: 1., y: 2., z: 3. }, Vec3::ONE * 0.5);
+        let obj = to_obj_str(&faces);
+        let back = from_obj_str(&obj).unwrap();
+
+        assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 8);
+        assert_eq!(back.len(), faces.len());
+        assert!(back.iter().zip(faces.iter()).all(|(a, b)| a.vertices == b.vertices));
+    }
+
+    #[test]
+    fn stl_round_trip() {
+        let faces = bsp_cube_faces(Vec3::ZERO, Vec3::ONE);
+        let bytes = to_stl_bytes(&faces);
+        let triangles = from_stl_bytes(&bytes).unwrap();
+
+        assert_eq!(bytes.len(), STL_HEADER_LEN + 4 + 12 * STL_TRIANGLE_LEN);
+        assert_eq!(triangles.len(), 12);
+        assert_eq!(merge_coplanar_polygons(triangles).len(), 6);
+        assert!(matches!(from_stl_bytes(&bytes[..bytes.len() - 1]), Err(BspError::ParseError(_))));
+    }
+
+    #[test]
+    fn cube_from_indexed_arrays() {
+        let vertices: Vec<Vec3> = (0..8).map(|i| {
+            let bit = |b: usize| if i & b != 0 { 1. } else { -1. };
+            return Vec3 { x: bit(1), y: bit(2), z: bit(4) };
+        }).collect();
+        let faces = vec![vec![0, 4, 6, 2], vec![1, 3, 7, 5], vec![0, 1, 5, 4], vec![2, 6, 7, 3], vec![0, 2, 3, 1], vec![4, 5, 7, 6]];
+        let polygons = from_indexed(&vertices, &faces).unwrap();
+
+        assert_eq!(polygons.len(), 6);
+        assert!((from_polygons(polygons).unwrap().volume() - 8.).abs() < 1e-4);
+        assert!(matches!(from_indexed(&vertices, &[vec![0, 1, 8]]), Err(BspError::ParseError(_))));
+    }
+}
@@ -0,0 +1,59 @@
+//! Binary space partitioning trees for polygonal solids: building a tree from
+//! polygons, constructive solid geometry between trees, and queries such as
+//! point containment, raycasts and mass properties.
+//!
+//! ```
+//! use bsp::prelude::*;
+//!
+//! let faces = bsp_cube_faces(Vec3::ZERO, Vec3::ONE);
+//! let tree = from_polygons(faces).unwrap();
+//!
+//! assert!(tree.point_in_solid(Vec3 { x: 0.5, y: 0.5, z: 0.5 }));
+//! assert!(!tree.point_in_solid(Vec3 { x: 2., y: 0., z: 0. }));
+//! assert!((tree.volume() - 8.).abs() < 1e-9);
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::needless_return, clippy::upper_case_acronyms)]
+
+extern crate alloc;
+
+pub mod error;
+
+// Both precisions compile the same sources. Each fixes its own scalar type
+// and tolerances, so `f32` and `f64` trees are distinct types that one
+// program can use side by side.
+#[allow(clippy::duplicate_mod)]
+pub mod f32;
+#[allow(clippy::duplicate_mod)]
+pub mod f64;
+
+pub use crate::f64::{csg, math, plane, polygon, shapes, tree};
+#[cfg(feature = "std")]
+pub use crate::f64::io;
+
+/// The `f64` prelude.
+pub mod prelude {
+    pub use crate::f64::prelude::*;
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn cube_builds_at_both_precisions() {
+        use crate::f32::prelude as single;
+        use crate::f64::prelude as double;
+
+        let faces = single::bsp_cube_faces(single::Vec3::ZERO, single::Vec3::ONE);
+        let tree: single::BSPNode = single::from_polygons(faces).unwrap();
+        let volume: f32 = tree.volume();
+        assert!((volume - 8.).abs() < 1e-4);
+        assert!(tree.point_in_solid(single::Vec3 { x: 0.5, y: 0.5, z: 0.5 }));
+
+        let faces = double::bsp_cube_faces(double::Vec3::ZERO, double::Vec3::ONE);
+        let tree: double::BSPNode = double::from_polygons(faces).unwrap();
+        let volume: f64 = tree.volume();
+        assert!((volume - 8.).abs() < 1e-12);
+        assert!(tree.point_in_solid(double::Vec3 { x: 0.5, y: 0.5, z: 0.5 }));
+    }
+}
@@ -0,0 +1,753 @@
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+use super::plane::BSPPlane;
+
+pub use super::scalar::{Scalar, ScalarBits};
+pub(crate) use super::scalar::NORMALIZE_EPS;
+
+/// The float functions `core` lacks, taken from libm when `std` is off. With
+/// `std`, which test builds always link, the inherent methods of the same
+/// names are used instead, so only a library build for a target without std
+/// compiles this; CI runs one.
+#[cfg(not(any(feature = "std", test)))]
+pub(crate) trait ScalarMath {
+    fn sqrt(self) -> Scalar;
+    fn sin(self) -> Scalar;
+    fn cos(self) -> Scalar;
+    fn acos(self) -> Scalar;
+    fn floor(self) -> Scalar;
+    fn sin_cos(self) -> (Scalar, Scalar);
+}
+
+#[cfg(not(any(feature = "std", test)))]
+impl ScalarMath for Scalar {
+    fn sqrt(self) -> Scalar {
+        return libm::Libm::<Scalar>::sqrt(self);
+    }
+
+    fn sin(self) -> Scalar {
+        return libm::Libm::<Scalar>::sin(self);
+    }
+
+    fn cos(self) -> Scalar {
+        return libm::Libm::<Scalar>::cos(self);
+    }
+
+    fn acos(self) -> Scalar {
+        return libm::Libm::<Scalar>::acos(self);
+    }
+
+    fn floor(self) -> Scalar {
+        return libm::Libm::<Scalar>::floor(self);
+    }
+
+    fn sin_cos(self) -> (Scalar, Scalar) {
+        return libm::Libm::<Scalar>::sincos(self);
+    }
+}
+
+/// `==` and `Hash` compare the bit patterns of the components, which is what
+/// dedup in hash sets wants: `0.0` and `-0.0` are distinct keys and a NaN
+/// equals itself. Geometry produced by splitting should be compared with
+/// `approx_eq`.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "[Scalar; 3]", into = "[Scalar; 3]"))]
+pub struct Vec3 {
+    pub x: Scalar, 
+    pub y: Scalar, 
+    pub z: Scalar
+}
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3 { x: 0., y: 0., z: 0. };
+    pub const ONE: Vec3 = Vec3 { x: 1., y: 1., z: 1. };
+    pub const UNIT_X: Vec3 = Vec3 { x: 1., y: 0., z: 0. };
+    pub const UNIT_Y: Vec3 = Vec3 { x: 0., y: 1., z: 0. };
+    pub const UNIT_Z: Vec3 = Vec3 { x: 0., y: 0., z: 1. };
+
+    pub fn sub(&self, other: Vec3) -> Vec3 {
+        return *self - other;
+    }
+
+    pub fn add(&self, other: Vec3) -> Vec3 {
+        return *self + other;
+    }
+
+    pub fn sum(iter: impl Iterator<Item = Vec3>) -> Vec3 {
+        return iter.fold(Vec3::ZERO, |acc, v| acc + v);
+    }
+
+    pub fn cross(&self, other: Vec3) -> Vec3 {
+        return Vec3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        };
+    }
+
+    pub fn dot(&self, other: Vec3) -> Scalar {
+        return self.x * other.x + self.y * other.y + self.z * other.z;
+    }
+
+    /// Scalar triple product `a · (b × c)`.
+    pub fn triple(a: Vec3, b: Vec3, c: Vec3) -> Scalar {
+        return a.dot(b.cross(c));
+    }
+
+    /// Angle in radians, in `[0, π]`. NaN if either vector has zero length.
+    pub fn angle_between(&self, other: Vec3) -> Scalar {
+        let cos = self.dot(other) / (self.len() * other.len());
+        return cos.clamp(-1., 1.).acos();
+    }
+
+    /// `t = 0` returns `self` and `t = 1` returns `other`.
+    pub fn lerp(&self, other: Vec3, t: Scalar) -> Vec3 {
+        return *self * (1. - t) + other * t;
+    }
+
+    pub fn midpoint(&self, other: Vec3) -> Vec3 {
+        return self.lerp(other, 0.5);
+    }
+
+    /// Returns `ZERO` when projecting onto a zero-length vector.
+    pub fn project_onto(&self, other: Vec3) -> Vec3 {
+        let len_squared = other.len_squared();
+
+        if len_squared == 0. {
+            return Vec3::ZERO;
+        }
+
+        return other * (self.dot(other) / len_squared);
+    }
+
+    pub fn reject_from(&self, other: Vec3) -> Vec3 {
+        return *self - self.project_onto(other);
+    }
+
+    /// Reflects the vector off a surface with the given unit-length normal.
+    pub fn reflect(&self, normal: Vec3) -> Vec3 {
+        return *self - normal * (2. * self.dot(normal));
+    }
+
+    /// Mirrors the point through the plane.
+    pub fn reflect_across_plane(&self, plane: BSPPlane) -> Vec3 {
+        return *self - plane.n * (2. * (plane.n.dot(*self) - plane.d));
+    }
+
+    /// Rotates by `angle` radians counter-clockwise around `axis`, which is
+    /// normalized internally.
+    pub fn rotate_around_axis(&self, axis: Vec3, angle: Scalar) -> Vec3 {
+        let k = axis.normalized();
+        let (sin, cos) = angle.sin_cos();
+
+        return *self * cos + k.cross(*self) * sin + k * (k.dot(*self) * (1. - cos));
+    }
+
+    pub fn scale(&self, scale: Scalar) -> Vec3 {
+        return *self * scale;
+    }
+
+    /// Divides by the length, so a zero-length vector yields NaN components.
+    /// Use `try_normalized` when the input may be degenerate.
+    pub fn normalized(&self) -> Vec3 {
+        let len = self.len();
+        return self.scale(1. / len);
+    }
+
+    pub fn try_normalized(&self) -> Option<Vec3> {
+        let len = self.len();
+
+        if len < NORMALIZE_EPS {
+            return None;
+        }
+
+        return Some(self.scale(1. / len));
+    }
+
+    pub fn min(&self, other: Vec3) -> Vec3 {
+        return Vec3 {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z),
+        };
+    }
+
+    pub fn max(&self, other: Vec3) -> Vec3 {
+        return Vec3 {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z),
+        };
+    }
+
+    pub fn abs(&self) -> Vec3 {
+        return Vec3 {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+        };
+    }
+
+    pub fn clamp_length(&self, max: Scalar) -> Vec3 {
+        let len = self.len();
+
+        if len <= max {
+            return *self;
+        }
+
+        return self.scale(max / len);
+    }
+
+    /// A zero-length vector has no direction and is returned unchanged.
+    pub fn with_length(&self, len: Scalar) -> Vec3 {
+        return match self.try_normalized() {
+            Some(n) => n.scale(len),
+            None => *self,
+        };
+    }
+
+    pub fn is_finite(&self) -> bool {
+        return self.x.is_finite() && self.y.is_finite() && self.z.is_finite();
+    }
+
+    pub fn has_nan(&self) -> bool {
+        return self.x.is_nan() || self.y.is_nan() || self.z.is_nan();
+    }
+
+    pub fn to_bits_key(self) -> [ScalarBits; 3] {
+        return [self.x.to_bits(), self.y.to_bits(), self.z.to_bits()];
+    }
+
+    pub fn approx_eq(&self, other: Vec3, eps: Scalar) -> bool {
+        return (self.x - other.x).abs() <= eps
+            && (self.y - other.y).abs() <= eps
+            && (self.z - other.z).abs() <= eps;
+    }
+
+    pub fn len(&self) -> Scalar {
+        return self.len_squared().sqrt();
+    }
+
+    pub fn len_squared(&self) -> Scalar {
+        return self.dot(*self);
+    }
+
+    pub fn dist(&self, other: Vec3) -> Scalar {
+        return self.dist_squared(other).sqrt();
+    }
+
+    pub fn dist_squared(&self, other: Vec3) -> Scalar {
+        return (*self - other).len_squared();
+    }
+}
+
+impl Default for Vec3 {
+    fn default() -> Vec3 {
+        return Vec3::ZERO;
+    }
+}
+
+impl PartialEq for Vec3 {
+    fn eq(&self, other: &Vec3) -> bool {
+        return self.to_bits_key() == other.to_bits_key();
+    }
+}
+
+impl Eq for Vec3 {}
+
+impl Hash for Vec3 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bits_key().hash(state);
+    }
+}
+
+impl fmt::Display for Vec3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match f.precision() {
+            Some(p) => write!(f, "({:.*}, {:.*}, {:.*})", p, self.x, p, self.y, p, self.z),
+            None => write!(f, "({}, {}, {})", self.x, self.y, self.z),
+        };
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, other: Vec3) -> Vec3 {
+        return Vec3 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        };
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, other: Vec3) -> Vec3 {
+        return Vec3 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        };
+    }
+}
+
+impl Mul<Scalar> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, scale: Scalar) -> Vec3 {
+        return Vec3 {
+            x: self.x * scale,
+            y: self.y * scale,
+            z: self.z * scale,
+        };
+    }
+}
+
+// Component-wise product. Use `dot` or `cross` for the other vector products.
+impl Mul<Vec3> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, other: Vec3) -> Vec3 {
+        return Vec3 {
+            x: self.x * other.x,
+            y: self.y * other.y,
+            z: self.z * other.z,
+        };
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+
+    fn neg(self) -> Vec3 {
+        return Vec3 {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        };
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, other: Vec3) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, other: Vec3) {
+        *self = *self - other;
+    }
+}
+
+impl Index<usize> for Vec3 {
+    type Output = Scalar;
+
+    fn index(&self, i: usize) -> &Scalar {
+        return match i {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index out of range: {}", i),
+        };
+    }
+}
+
+impl IndexMut<usize> for Vec3 {
+    fn index_mut(&mut self, i: usize) -> &mut Scalar {
+        return match i {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Vec3 index out of range: {}", i),
+        };
+    }
+}
+
+impl From<[Scalar; 3]> for Vec3 {
+    fn from(v: [Scalar; 3]) -> Vec3 {
+        return Vec3 { x: v[0], y: v[1], z: v[2] };
+    }
+}
+
+impl From<(Scalar, Scalar, Scalar)> for Vec3 {
+    fn from(v: (Scalar, Scalar, Scalar)) -> Vec3 {
+        return Vec3 { x: v.0, y: v.1, z: v.2 };
+    }
+}
+
+impl From<Vec3> for [Scalar; 3] {
+    fn from(v: Vec3) -> [Scalar; 3] {
+        return [v.x, v.y, v.z];
+    }
+}
+
+/// An affine transform acting on column vectors, stored row by row so that
+/// `m[r][c]` is row `r`, column `c`. The last row is `0 0 0 1` for every
+/// matrix built here, and `mul_point` and `mul_vector` ignore it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mat4 {
+    pub m: [[Scalar; 4]; 4]
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 { m: [
+        [1., 0., 0., 0.],
+        [0., 1., 0., 0.],
+        [0., 0., 1., 0.],
+        [0., 0., 0., 1.],
+    ] };
+
+    pub fn identity() -> Mat4 {
+        return Mat4::IDENTITY;
+    }
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        return Mat4 { m: [
+            [1., 0., 0., offset.x],
+            [0., 1., 0., offset.y],
+            [0., 0., 1., offset.z],
+            [0., 0., 0., 1.],
+        ] };
+    }
+
+    /// Rotates by `angle` radians counter-clockwise around `axis`, like
+    /// `Vec3::rotate_around_axis`.
+    pub fn rotation(axis: Vec3, angle: Scalar) -> Mat4 {
+        let k = axis.normalized();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1. - cos;
+
+        return Mat4 { m: [
+            [cos + k.x * k.x * t, k.x * k.y * t - k.z * sin, k.x * k.z * t + k.y * sin, 0.],
+            [k.y * k.x * t + k.z * sin, cos + k.y * k.y * t, k.y * k.z * t - k.x * sin, 0.],
+            [k.z * k.x * t - k.y * sin, k.z * k.y * t + k.x * sin, cos + k.z * k.z * t, 0.],
+            [0., 0., 0., 1.],
+        ] };
+    }
+
+    /// Scales each axis by the matching component of `factors`.
+    pub fn scale(factors: Vec3) -> Mat4 {
+        return Mat4 { m: [
+            [factors.x, 0., 0., 0.],
+            [0., factors.y, 0., 0.],
+            [0., 0., factors.z, 0.],
+            [0., 0., 0., 1.],
+        ] };
+    }
+
+    /// Mirrors through `plane`, like `Vec3::reflect_across_plane`.
+    pub fn reflection(plane: BSPPlane) -> Mat4 {
+        let n = plane.n;
+        let t = n * (2. * plane.d);
+
+        return Mat4 { m: [
+            [1. - 2. * n.x * n.x, -2. * n.x * n.y, -2. * n.x * n.z, t.x],
+            [-2. * n.y * n.x, 1. - 2. * n.y * n.y, -2. * n.y * n.z, t.y],
+            [-2. * n.z * n.x, -2. * n.z * n.y, 1. - 2. * n.z * n.z, t.z],
+            [0., 0., 0., 1.],
+        ] };
+    }
+
+    pub fn mul_point(&self, p: Vec3) -> Vec3 {
+        return self.mul_vector(p) + Vec3 { x: self.m[0][3], y: self.m[1][3], z: self.m[2][3] };
+    }
+
+    /// Applies the transform without its translation, as for a direction or
+    /// an offset between two points.
+    pub fn mul_vector(&self, v: Vec3) -> Vec3 {
+        let row = |r: usize| self.m[r][0] * v.x + self.m[r][1] * v.y + self.m[r][2] * v.z;
+
+        return Vec3 { x: row(0), y: row(1), z: row(2) };
+    }
+
+    /// Maps a surface normal through the inverse transpose of the linear part
+    /// and renormalizes it, so it stays perpendicular to the transformed
+    /// surface under non-uniform scale. Singular transforms yield NaN.
+    pub fn mul_normal(&self, n: Vec3) -> Vec3 {
+        let [c0, c1, c2] = self.columns();
+        let n = (c1.cross(c2) * n.x + c2.cross(c0) * n.y + c0.cross(c1) * n.z) * (1. / self.determinant());
+
+        return n.normalized();
+    }
+
+    /// The determinant of the linear part. It is negative when the transform
+    /// mirrors and zero when it flattens space.
+    pub fn determinant(&self) -> Scalar {
+        let [c0, c1, c2] = self.columns();
+
+        return Vec3::triple(c0, c1, c2);
+    }
+
+    fn columns(&self) -> [Vec3; 3] {
+        let column = |c: usize| Vec3 { x: self.m[0][c], y: self.m[1][c], z: self.m[2][c] };
+
+        return [column(0), column(1), column(2)];
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Mat4 {
+        return Mat4::IDENTITY;
+    }
+}
+
+// `a * b` applies `b` first, then `a`.
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        let mut m = [[0.; 4]; 4];
+
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, entry) in row.iter_mut().enumerate() {
+                *entry = (0..4).map(|k| self.m[r][k] * other.m[k][c]).sum();
+            }
+        }
+
+        return Mat4 { m };
+    }
+}
+
+/// Two unit vectors perpendicular to `axis` (and to each other) such that
+/// `u × v` points along `axis`.
+pub fn perpendicular_basis(axis: Vec3) -> (Vec3, Vec3) {
+    let helper = if axis.x.abs() < 0.9 { Vec3::UNIT_X } else { Vec3::UNIT_Y };
+    let u = axis.cross(helper).normalized();
+    let v = axis.cross(u);
+
+    return (u, v);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::plane::{intersect_segment_plane, plane_from_point_normal, PLANE_THICKNESS_EPS};
+    use super::super::scalar::PI;
+    use super::super::plane::bsp_plane_by_three_points;
+    use alloc::format;
+
+    #[test]
+    fn operators() {
+        let a = Vec3 { x: 1., y: -2., z: 3. };
+        let b = Vec3 { x: 0.5, y: 4., z: -1. };
+        let mut c = a;
+
+        c += b;
+        c -= a;
+
+        assert_eq!(a + b, b + a);
+        assert_eq!(a - b, -(b - a));
+        assert_eq!(-a, a.scale(-1.0));
+        assert_eq!(a * 2., a.scale(2.));
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    fn add_then_sub_round_trips() {
+        let a = Vec3 { x: 0.1, y: 0.2, z: 0.3 };
+        let b = Vec3 { x: 7.5, y: -3.25, z: 1e3 };
+        let back = a.add(b).sub(b);
+
+        assert!((back.x - a.x).abs() < 1e-4 && (back.y - a.y).abs() < 1e-4 && (back.z - a.z).abs() < 1e-4);
+        assert_eq!(Vec3::sum([a, b, Vec3::ONE].into_iter()), a + b + Vec3::ONE);
+        assert_eq!(Vec3::sum(core::iter::empty()), Vec3::ZERO);
+    }
+
+    #[test]
+    fn dist_of_a_3_4_5_triangle() {
+        let a = Vec3 { x: 1., y: 1., z: -2. };
+        let b = Vec3 { x: 4., y: 5., z: -2. };
+
+        assert_eq!(a.dist_squared(b), 25.);
+        assert_eq!(a.dist(b), 5.);
+        assert_eq!(b.dist(a), 5.);
+    }
+
+    #[test]
+    fn len_squared_matches_len() {
+        for v in [Vec3::ZERO, Vec3::ONE, Vec3 { x: 3., y: -4., z: 12. }, Vec3 { x: 0.1, y: 0.7, z: -0.3 }] {
+            assert!((v.len_squared() - v.len() * v.len()).abs() <= 1e-5 * v.len_squared().max(1.));
+        }
+
+        assert_eq!(Vec3 { x: 3., y: -4., z: 12. }.len_squared(), 169.);
+    }
+
+    #[test]
+    fn shared_edge_intersections_are_approx_eq() {
+        let plane = plane_from_point_normal(Vec3 { x: 0.3, y: 0.1, z: 0.7 }, Vec3 { x: 0.2, y: 1., z: 0.3 }).unwrap();
+        let a = Vec3 { x: -1.3, y: -2.9, z: 0.1 };
+        let b = Vec3 { x: 2.7, y: 3.3, z: 1.9 };
+
+        let forward = intersect_segment_plane(a, b, plane).unwrap();
+        let backward = intersect_segment_plane(b, a, plane).unwrap();
+
+        assert!(forward.approx_eq(backward, PLANE_THICKNESS_EPS));
+        assert!(!forward.approx_eq(forward + Vec3::UNIT_X, PLANE_THICKNESS_EPS));
+    }
+
+    #[test]
+    fn angle_between_axes() {
+        let a = Vec3 { x: 2., y: 0., z: 0. };
+
+        assert!((a.angle_between(Vec3::UNIT_Y) - PI / 2.).abs() < 1e-5);
+        assert!(a.angle_between(Vec3::UNIT_X * 5.).abs() < 1e-5);
+        assert!((a.angle_between(-Vec3::UNIT_X) - PI).abs() < 1e-5);
+    }
+
+    #[test]
+    fn reflect_off_the_xy_plane() {
+        let v = Vec3 { x: 1., y: -2., z: -3. };
+
+        assert_eq!(v.reflect(Vec3::UNIT_Z), Vec3 { x: 1., y: -2., z: 3. });
+        assert_eq!(v.reflect(Vec3::UNIT_Z).reflect(Vec3::UNIT_Z), v);
+        assert_eq!(Vec3::UNIT_X.reflect(Vec3::UNIT_Z), Vec3::UNIT_X);
+    }
+
+    #[test]
+    fn projection_plus_rejection_is_self() {
+        let v = Vec3 { x: 3., y: -1., z: 2. };
+        let onto = Vec3 { x: 1., y: 2., z: -0.5 };
+        let projected = v.project_onto(onto);
+        let rejected = v.reject_from(onto);
+
+        assert!((projected + rejected).approx_eq(v, 1e-5));
+        assert!(rejected.dot(onto).abs() < 1e-5);
+        assert!(projected.cross(onto).len() < 1e-5);
+        assert_eq!(v.project_onto(Vec3::ZERO), Vec3::ZERO);
+    }
+
+    #[test]
+    fn component_wise_min_max_abs() {
+        let a = Vec3 { x: -1., y: 5., z: -0.5 };
+        let b = Vec3 { x: 2., y: -3., z: -0.25 };
+
+        assert_eq!(a.min(b), Vec3 { x: -1., y: -3., z: -0.5 });
+        assert_eq!(a.max(b), Vec3 { x: 2., y: 5., z: -0.25 });
+        assert_eq!(a.abs(), Vec3 { x: 1., y: 5., z: 0.5 });
+        assert_eq!(b.abs(), Vec3 { x: 2., y: 3., z: 0.25 });
+    }
+
+    #[test]
+    fn array_round_trip() {
+        let arr: [Scalar; 3] = [1.5, -2., 0.25];
+        let back: [Scalar; 3] = Vec3::from(arr).into();
+
+        assert_eq!(back, arr);
+        assert_eq!(Vec3::from((1.5, -2., 0.25)), Vec3::from(arr));
+    }
+
+    #[test]
+    fn degenerate_vectors_do_not_normalize() {
+        let a = Vec3 { x: 0., y: 0., z: 0. };
+        let b = Vec3 { x: 1., y: 1., z: 1. };
+        let c = Vec3 { x: 3., y: 3., z: 3. };
+
+        assert_eq!(Vec3::ZERO.try_normalized(), None);
+        assert!(Vec3 { x: 0., y: 3., z: 4. }.try_normalized().unwrap().approx_eq(Vec3 { x: 0., y: 0.6, z: 0.8 }, 1e-6));
+        assert!(bsp_plane_by_three_points(a, b, c).is_none());
+    }
+
+    #[test]
+    fn clamp_length_only_shrinks() {
+        let short = Vec3 { x: 0.3, y: 0.4, z: 0. };
+        let long = Vec3 { x: 30., y: 40., z: 0. };
+
+        assert_eq!(short.clamp_length(1.), short);
+        assert!((long.clamp_length(2.).len() - 2.).abs() < 1e-5);
+        assert!(long.clamp_length(2.).approx_eq(Vec3 { x: 1.2, y: 1.6, z: 0. }, 1e-5));
+        assert_eq!(Vec3::ZERO.clamp_length(1.), Vec3::ZERO);
+        assert_eq!(Vec3::ZERO.with_length(3.), Vec3::ZERO);
+    }
+
+    #[test]
+    fn display_honors_precision() {
+        let v = Vec3 { x: 1., y: -2.5, z: 0.125 };
+
+        assert_eq!(format!("{:.2}", v), "(1.00, -2.50, 0.12)");
+        assert_eq!(format!("{}", v), "(1, -2.5, 0.125)");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hash_set_dedups_vertices() {
+        let vertices = [Vec3::ONE, Vec3::ZERO, Vec3::ONE, Vec3 { x: 1., y: 1., z: 1. }, Vec3::UNIT_X];
+        let set: std::collections::HashSet<Vec3> = vertices.into_iter().collect();
+
+        assert_eq!(set.len(), 3);
+        assert_ne!(Vec3 { x: -0., y: 0., z: 0. }.to_bits_key(), Vec3::ZERO.to_bits_key());
+    }
+
+    #[test]
+    fn triple_product_of_the_axes() {
+        assert_eq!(Vec3::triple(Vec3::UNIT_X, Vec3::UNIT_Y, Vec3::UNIT_Z), 1.);
+        assert_eq!(Vec3::triple(Vec3::UNIT_Y, Vec3::UNIT_X, Vec3::UNIT_Z), -1.);
+        assert_eq!(Vec3::triple(Vec3::UNIT_X, Vec3::UNIT_X, Vec3::UNIT_Z), 0.);
+    }
+
+    #[test]
+    fn midpoint_is_halfway() {
+        let a = Vec3 { x: -1., y: 2., z: 4. };
+        let b = Vec3 { x: 3., y: 2., z: -4. };
+
+        assert_eq!(a.midpoint(b), Vec3 { x: 1., y: 2., z: 0. });
+        assert_eq!(a.midpoint(b), a.lerp(b, 0.5));
+        assert_eq!(a.lerp(b, 0.), a);
+        assert_eq!(a.lerp(b, 1.), b);
+    }
+
+    #[test]
+    fn nan_and_infinite_components() {
+        let nan = Vec3 { x: 1., y: Scalar::NAN, z: 0. };
+        let infinite = Vec3 { x: Scalar::INFINITY, y: 0., z: 0. };
+        let finite = Vec3 { x: 1., y: -2., z: 3. };
+
+        assert!(nan.has_nan() && !nan.is_finite());
+        assert!(!infinite.has_nan() && !infinite.is_finite());
+        assert!(!finite.has_nan() && finite.is_finite());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let v = Vec3 { x: 1.5, y: -2., z: 0.25 };
+        let json = serde_json::to_string(&v).unwrap();
+
+        assert_eq!(json, "[1.5,-2.0,0.25]");
+        assert_eq!(serde_json::from_str::<Vec3>(&json).unwrap(), v);
+    }
+
+    #[test]
+    fn index_reads_and_writes_components() {
+        let mut v = Vec3 { x: 1., y: 2., z: 3. };
+
+        for i in 0..3 {
+            assert_eq!(v[i], (i + 1) as Scalar);
+            v[i] *= -1.;
+        }
+
+        assert_eq!(v, Vec3 { x: -1., y: -2., z: -3. });
+    }
+
+    #[test]
+    #[should_panic(expected = "Vec3 index out of range: 3")]
+    fn index_3_panics() {
+        let _ = Vec3::ZERO[3];
+    }
+
+    #[test]
+    fn rotate_x_around_z() {
+        let rotated = Vec3::UNIT_X.rotate_around_axis(Vec3::UNIT_Z, PI / 2.);
+
+        assert!(rotated.approx_eq(Vec3::UNIT_Y, 1e-6));
+        assert!(Vec3::UNIT_X.rotate_around_axis(Vec3::UNIT_Z * 3., PI).approx_eq(-Vec3::UNIT_X, 1e-6));
+        assert!(Vec3::UNIT_Z.rotate_around_axis(Vec3::UNIT_Z, 1.).approx_eq(Vec3::UNIT_Z, 1e-6));
+    }
+}
@@ -0,0 +1,313 @@
+use super::math::{Mat4, Scalar, Vec3, NORMALIZE_EPS};
+#[cfg(not(any(feature = "std", test)))]
+use super::math::ScalarMath;
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BSPPlane {
+    pub n: Vec3,
+    pub d: Scalar
+}
+
+impl BSPPlane {
+    /// Swaps the front and back half-spaces.
+    pub fn flip(&self) -> BSPPlane {
+        return BSPPlane { n: self.n.scale(-1.0), d: -self.d };
+    }
+
+    /// True when the normals are within `angle_eps` radians of each other and
+    /// the offsets differ by at most `dist_eps`.
+    pub fn coplanar_with(&self, other: BSPPlane, angle_eps: Scalar, dist_eps: Scalar) -> bool {
+        return self.n.dot(other.n) >= angle_eps.cos() && (self.d - other.d).abs() <= dist_eps;
+    }
+
+    /// True when both planes describe the same surface but face opposite ways.
+    pub fn opposite_of(&self, other: BSPPlane, angle_eps: Scalar, dist_eps: Scalar) -> bool {
+        return self.coplanar_with(other.flip(), angle_eps, dist_eps);
+    }
+
+    /// The plane through the transformed surface, with its normal mapped by
+    /// `Mat4::mul_normal`.
+    pub fn transformed(&self, m: &Mat4) -> BSPPlane {
+        let n = m.mul_normal(self.n);
+
+        return BSPPlane { n, d: n.dot(m.mul_point(self.n * self.d)) };
+    }
+}
+
+/// Polygons are wound counter-clockwise when viewed from the front, so the
+/// normal follows the right-hand rule over `a`, `b`, `c`. Returns `None` when
+/// the points are coincident or collinear.
+pub fn bsp_plane_by_three_points(a: Vec3, b: Vec3, c: Vec3) -> Option<BSPPlane> {
+    let d0 = b - a;
+    let d1 = c - a;
+
+    let n = d0.cross(d1).try_normalized()?;
+    let d = a.dot(n);
+
+    return Some(BSPPlane { n,  d });
+}
+
+/// Returns `None` for a zero-length normal.
+pub fn plane_from_point_normal(point: Vec3, normal: Vec3) -> Option<BSPPlane> {
+    let n = normal.try_normalized()?;
+
+    return Some(BSPPlane { n, d: point.dot(n) });
+}
+
+/// Fits a plane to all of the vertices using Newell's method, which stays
+/// stable for non-triangular and slightly non-planar polygons. The normal has
+/// the same orientation as `bsp_plane_by_three_points`. Returns `None` when
+/// the vertices enclose no area.
+pub fn plane_from_polygon(vertices: &[Vec3]) -> Option<BSPPlane> {
+    if vertices.len() < 3 {
+        return None;
+    }
+
+    let n = newell_normal(vertices).try_normalized()?;
+    let centroid = Vec3::sum(vertices.iter().copied()).scale(1. / vertices.len() as Scalar);
+
+    return Some(BSPPlane { n, d: centroid.dot(n) });
+}
+
+/// Sum of the cross products of consecutive vertices. Its direction is the
+/// polygon normal and its length is twice the enclosed area. Fewer than three
+/// vertices enclose nothing, so they give `Vec3::ZERO`.
+pub fn newell_normal(vertices: &[Vec3]) -> Vec3 {
+    if vertices.len() < 3 {
+        return Vec3::ZERO;
+    }
+
+    let mut normal = Vec3::ZERO;
+    let mut a = vertices[vertices.len() - 1];
+
+    for b in vertices.iter() {
+        normal += a.cross(*b);
+        a = *b;
+    }
+
+    return normal;
+}
+
+/// Returns `None` when the segment misses the plane or runs parallel to it.
+pub fn intersect_segment_plane(a: Vec3, b: Vec3, plane: BSPPlane) -> Option<Vec3> {
+    let ab = b - a;
+    let denom = plane.n.dot(ab);
+
+    if denom.abs() < NORMALIZE_EPS {
+        return None;
+    }
+
+    let t = (plane.d - plane.n.dot(a)) / denom;
+
+    if (0.0..=1.0).contains(&t) {
+        return Some(a.lerp(b, t));
+    }
+
+    return None;
+}
+
+pub fn closest_point_on_segment(a: Vec3, b: Vec3, p: Vec3) -> Vec3 {
+    let ab = b - a;
+    let len_squared = ab.len_squared();
+
+    if len_squared == 0. {
+        return a;
+    }
+
+    return a.lerp(b, ((p - a).dot(ab) / len_squared).clamp(0., 1.));
+}
+
+/// Returns a point on the line shared by both planes and the line's unit
+/// direction, or `None` when the planes are parallel.
+pub fn intersect_planes(a: BSPPlane, b: BSPPlane) -> Option<(Vec3, Vec3)> {
+    let u = a.n.cross(b.n);
+    let len_squared = u.len_squared();
+
+    if len_squared.sqrt() < NORMALIZE_EPS {
+        return None;
+    }
+
+    let point = (b.n.cross(u) * a.d + u.cross(a.n) * b.d).scale(1. / len_squared);
+
+    return Some((point, u.normalized()));
+}
+
+/// Solves for the single point shared by three planes with Cramer's rule.
+/// Returns `None` when two or more of the planes are parallel.
+pub fn intersect_three_planes(a: BSPPlane, b: BSPPlane, c: BSPPlane) -> Option<Vec3> {
+    let det = Vec3::triple(a.n, b.n, c.n);
+
+    if det.abs() < NORMALIZE_EPS {
+        return None;
+    }
+
+    let p = b.n.cross(c.n) * a.d + c.n.cross(a.n) * b.d + a.n.cross(b.n) * c.d;
+
+    return Some(p.scale(1. / det));
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PointPlaneSide {
+    COPLANAR,
+    FRONT,
+    BACK,
+}
+
+pub use super::scalar::PLANE_THICKNESS_EPS;
+
+/// Positive in front of the plane, negative behind it.
+pub fn signed_distance(plane: BSPPlane, p: Vec3) -> Scalar {
+    return plane.n.dot(p) - plane.d;
+}
+
+pub fn project_point_onto_plane(plane: BSPPlane, p: Vec3) -> Vec3 {
+    return p - plane.n * signed_distance(plane, p);
+}
+
+pub fn classify_point_to_plane(plane: BSPPlane, p: Vec3, eps: Scalar) -> PointPlaneSide {
+    let dist = signed_distance(plane, p);
+
+    if dist > eps {
+        return PointPlaneSide::FRONT;
+    } else if dist < -eps {
+        return PointPlaneSide::BACK;
+    }
+
+    return PointPlaneSide::COPLANAR;
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use super::super::scalar::PI;
+
+    #[test]
+    fn signed_distance_to_the_xy_plane() {
+        let plane = BSPPlane { n: Vec3::UNIT_Z, d: 0. };
+
+        assert_eq!(signed_distance(plane, Vec3 { x: 3., y: -1., z: 2. }), 2.);
+        assert_eq!(signed_distance(plane, Vec3 { x: 3., y: -1., z: 0. }), 0.);
+        assert_eq!(signed_distance(plane, Vec3 { x: 3., y: -1., z: -0.5 }), -0.5);
+        assert_eq!(signed_distance(BSPPlane { n: Vec3::UNIT_Z, d: 1. }, Vec3::ZERO), -1.);
+    }
+
+    #[test]
+    fn flip_swaps_front_and_back() {
+        let plane = bsp_plane_by_three_points(Vec3::ZERO, Vec3::UNIT_X, Vec3::UNIT_Y).unwrap();
+        let p = Vec3 { x: 0.2, y: 0.3, z: 1. };
+
+        assert_eq!(classify_point_to_plane(plane, p, PLANE_THICKNESS_EPS), PointPlaneSide::FRONT);
+        assert_eq!(classify_point_to_plane(plane.flip(), p, PLANE_THICKNESS_EPS), PointPlaneSide::BACK);
+        assert_eq!(classify_point_to_plane(plane.flip(), Vec3::UNIT_X, PLANE_THICKNESS_EPS), PointPlaneSide::COPLANAR);
+    }
+
+    fn same_plane(a: BSPPlane, b: BSPPlane) -> bool {
+        return a.n.approx_eq(b.n, 1e-5) && (a.d - b.d).abs() < 1e-5;
+    }
+
+    #[test]
+    fn newell_plane_of_polygons() {
+        let quad = [Vec3 { x: 0., y: 0., z: 2. }, Vec3 { x: 2., y: 0., z: 2. }, Vec3 { x: 2., y: 1., z: 2. }, Vec3 { x: 0., y: 1., z: 2. }];
+        let pentagon: Vec<Vec3> = (0..5).map(|i| {
+            let (sin, cos) = (2. * PI * i as Scalar / 5.).sin_cos();
+            return Vec3 { x: cos, y: 0., z: sin };
+        }).collect();
+        let triangle = [Vec3 { x: 1., y: 0.5, z: 0. }, Vec3 { x: 0., y: 3., z: 1. }, Vec3 { x: -1., y: 0., z: 2. }];
+
+        assert!(same_plane(plane_from_polygon(&quad).unwrap(), BSPPlane { n: Vec3::UNIT_Z, d: 2. }));
+        assert!(same_plane(plane_from_polygon(&pentagon).unwrap(), BSPPlane { n: -Vec3::UNIT_Y, d: 0. }));
+        assert!(same_plane(plane_from_polygon(&triangle).unwrap(), bsp_plane_by_three_points(triangle[0], triangle[1], triangle[2]).unwrap()));
+        assert!(plane_from_polygon(&quad[..2]).is_none());
+    }
+
+    #[test]
+    fn coplanar_and_opposite_planes() {
+        let plane = BSPPlane { n: Vec3::UNIT_Z, d: 1. };
+        let tilted = BSPPlane { n: Vec3 { x: 0.01, y: 0., z: 1. }.normalized(), d: 1. };
+
+        assert!(plane.coplanar_with(BSPPlane { n: Vec3::UNIT_Z, d: 1. + 1e-7 }, 1e-3, 1e-5));
+        assert!(!plane.coplanar_with(plane.flip(), 1e-3, 1e-5));
+        assert!(plane.opposite_of(plane.flip(), 1e-3, 1e-5));
+        assert!(!plane.coplanar_with(tilted, 1e-3, 1e-5));
+        assert!(plane.coplanar_with(tilted, 0.1, 1e-5));
+        assert!(!plane.coplanar_with(BSPPlane { n: Vec3::UNIT_Z, d: 1.1 }, 1e-3, 1e-5));
+    }
+
+    #[test]
+    fn xy_and_xz_planes_meet_along_x() {
+        let xy = BSPPlane { n: Vec3::UNIT_Z, d: 0. };
+        let xz = BSPPlane { n: Vec3::UNIT_Y, d: 0. };
+        let (point, dir) = intersect_planes(xy, xz).unwrap();
+
+        assert!(point.approx_eq(Vec3::ZERO, 1e-6));
+        assert!(dir.cross(Vec3::UNIT_X).len() < 1e-6);
+        assert!(intersect_planes(xy, BSPPlane { n: Vec3::UNIT_Z, d: 3. }).is_none());
+    }
+
+    #[test]
+    fn coordinate_planes_meet_at_the_origin() {
+        let x = BSPPlane { n: Vec3::UNIT_X, d: 0. };
+        let y = BSPPlane { n: Vec3::UNIT_Y, d: 0. };
+        let z = BSPPlane { n: Vec3::UNIT_Z, d: 0. };
+
+        assert!(intersect_three_planes(x, y, z).unwrap().approx_eq(Vec3::ZERO, 1e-6));
+        assert!(intersect_three_planes(BSPPlane { d: 1., ..x }, BSPPlane { d: 2., ..y }, BSPPlane { d: 3., ..z }).unwrap().approx_eq(Vec3 { x: 1., y: 2., z: 3. }, 1e-6));
+        assert!(intersect_three_planes(x, y, BSPPlane { d: 1., ..x }).is_none());
+    }
+
+    #[test]
+    fn project_onto_the_xy_plane() {
+        let plane = BSPPlane { n: Vec3::UNIT_Z, d: 0. };
+        let projected = project_point_onto_plane(plane, Vec3 { x: 1.5, y: -2.25, z: 7. });
+
+        assert!(projected.z.abs() < 1e-6);
+        assert!(projected.approx_eq(Vec3 { x: 1.5, y: -2.25, z: 0. }, 1e-6));
+    }
+
+    #[test]
+    fn point_normal_matches_three_points() {
+        let (a, b, c) = (Vec3 { x: 1., y: 0., z: 0. }, Vec3 { x: 0., y: 1., z: 0. }, Vec3 { x: 0., y: 0., z: 1. });
+        let expected = bsp_plane_by_three_points(a, b, c).unwrap();
+
+        assert!(same_plane(plane_from_point_normal(b, Vec3::ONE * 2.).unwrap(), expected));
+        assert!(plane_from_point_normal(a, Vec3::ZERO).is_none());
+    }
+
+    #[test]
+    fn point_sides_compare_without_recursing() {
+        assert!(PointPlaneSide::FRONT != PointPlaneSide::BACK);
+        assert!(PointPlaneSide::COPLANAR == PointPlaneSide::COPLANAR);
+    }
+
+    #[test]
+    fn segment_in_the_plane_has_no_intersection() {
+        let plane = BSPPlane { n: Vec3::UNIT_Z, d: 1. };
+
+        assert_eq!(intersect_segment_plane(Vec3 { x: 0., y: 0., z: 1. }, Vec3 { x: 2., y: 1., z: 1. }, plane), None);
+        assert_eq!(intersect_segment_plane(Vec3::ZERO, Vec3::UNIT_X, plane), None);
+        assert!(intersect_segment_plane(Vec3::ZERO, Vec3::UNIT_Z * 2., plane).unwrap().approx_eq(Vec3::UNIT_Z, 1e-6));
+    }
+
+    #[test]
+    fn classify_a_known_point() {
+        let plane = BSPPlane { n: Vec3::UNIT_Z, d: 0. };
+
+        match classify_point_to_plane(plane, Vec3 { x: 1., y: 2., z: 3. }, PLANE_THICKNESS_EPS) {
+            PointPlaneSide::FRONT => {}
+            side => panic!("expected FRONT, got {:?}", side),
+        }
+        assert_eq!(classify_point_to_plane(plane, Vec3 { x: 1., y: 2., z: -3. }, PLANE_THICKNESS_EPS), PointPlaneSide::BACK);
+        assert_eq!(classify_point_to_plane(plane, Vec3 { x: 1., y: 2., z: 0. }, PLANE_THICKNESS_EPS), PointPlaneSide::COPLANAR);
+    }
+
+    #[test]
+    fn newell_normal_of_too_few_points_is_zero() {
+        assert_eq!(newell_normal(&[]), Vec3::ZERO);
+        assert_eq!(newell_normal(&[Vec3::ONE]), Vec3::ZERO);
+        assert_eq!(newell_normal(&[Vec3::ZERO, Vec3::ONE]), Vec3::ZERO);
+        assert_eq!(newell_normal(&[Vec3::ZERO, Vec3::UNIT_X, Vec3::UNIT_Y]), Vec3::UNIT_Z);
+    }
+}
@@ -0,0 +1,729 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::error::BspError;
+use super::math::{perpendicular_basis, Mat4, Scalar, Vec3, NORMALIZE_EPS};
+#[cfg(not(any(feature = "std", test)))]
+use super::math::ScalarMath;
+use super::plane::{classify_point_to_plane, closest_point_on_segment, newell_normal, plane_from_polygon, project_point_onto_plane, signed_distance, BSPPlane, PointPlaneSide, PLANE_THICKNESS_EPS};
+use super::tree::BuildConfig;
+
+/// `Send + Sync` when the `rayon` feature shares polygons between threads,
+/// and no bound at all otherwise, so single-threaded builds can hold `Rc`s
+/// and other types that stay on one thread.
+#[cfg(feature = "rayon")]
+pub trait MaybeSendSync: Send + Sync {}
+
+#[cfg(feature = "rayon")]
+impl<T: Send + Sync> MaybeSendSync for T {}
+
+#[cfg(not(feature = "rayon"))]
+pub trait MaybeSendSync {}
+
+#[cfg(not(feature = "rayon"))]
+impl<T> MaybeSendSync for T {}
+
+/// What a polygon stores at each corner: a position, plus any attributes such
+/// as texture coordinates or normals that splitting should carry over to the
+/// new corners it creates.
+pub trait Vertex: Clone + MaybeSendSync {
+    fn position(&self) -> Vec3;
+
+    /// The vertex `t` of the way from `self` to `other`. Positions must
+    /// interpolate linearly so the result lies on the edge.
+    fn interpolate(&self, other: &Self, t: Scalar) -> Self;
+
+    /// The vertex moved by `m`. Normals among the attributes should be mapped
+    /// with `Mat4::mul_normal`.
+    fn transform(&self, m: &Mat4) -> Self;
+}
+
+impl Vertex for Vec3 {
+    fn position(&self) -> Vec3 {
+        return *self;
+    }
+
+    fn interpolate(&self, other: &Vec3, t: Scalar) -> Vec3 {
+        return self.lerp(*other, t);
+    }
+
+    fn transform(&self, m: &Mat4) -> Vec3 {
+        return m.mul_point(*self);
+    }
+}
+
+/// Anything a polygon can carry as `data`, such as a material id or surface
+/// flags. Splitting clones it into both halves.
+pub trait PolygonData: Clone + MaybeSendSync {}
+
+impl<T: Clone + MaybeSendSync> PolygonData for T {}
+
+/// `BSPPolygon` on its own is a bare polygon of `Vec3` corners carrying `()`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BSPPolygon<V = Vec3, T = ()> {
+    pub plane: BSPPlane,
+    pub vertices: Vec<V>,
+    pub data: T
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PolygonPlaneSide {
+    FRONT,
+    BACK,
+    SPANNING,
+    COPLANAR
+}
+
+pub fn classify_polygon_by_plane<V: Vertex, T>(plane: BSPPlane, polygon: &BSPPolygon<V, T>, config: &BuildConfig<V, T>) -> PolygonPlaneSide {
+    let mut front_count = 0;
+    let mut back_count = 0;
+
+    for v in polygon.vertices.iter() {
+        let p = v.position();
+
+        match classify_point_to_plane(plane, p, config.eps_at(plane, p)) {
+            PointPlaneSide::FRONT => {
+                front_count += 1;
+            }
+            PointPlaneSide::BACK => {
+                back_count += 1;
+            }
+            _ => {}
+        }
+
+        if front_count != 0 && back_count != 0 {
+            return PolygonPlaneSide::SPANNING;
+        }
+    }
+
+    if front_count != 0 {
+        return PolygonPlaneSide::FRONT;
+    }
+
+    if back_count != 0 {
+        return PolygonPlaneSide::BACK;
+    }
+
+    return PolygonPlaneSide::COPLANAR;
+}
+
+/// Half the length of the Newell normal, which is exact for any simple
+/// planar polygon, convex or not, and 0 for fewer than three vertices.
+pub fn polygon_area<V: Vertex, T>(polygon: &BSPPolygon<V, T>) -> Scalar {
+    return newell_normal(&polygon.positions()).len() / 2.;
+}
+
+/// True for polygons with fewer than three vertices, or whose area is so
+/// small relative to their perimeter that they are thinner than `eps`.
+pub fn is_degenerate<V: Vertex, T>(polygon: &BSPPolygon<V, T>, eps: Scalar) -> bool {
+    if polygon.vertices.len() < 3 {
+        return true;
+    }
+
+    let positions = polygon.positions();
+    let mut perimeter = 0.;
+    let mut a = positions[positions.len() - 1];
+
+    for b in positions.iter() {
+        perimeter += a.dist(*b);
+        a = *b;
+    }
+
+    return polygon_area(polygon) <= eps * perimeter;
+}
+
+/// Fails with `EmptyInput` for an empty list, with `DegeneratePolygon` for one
+/// or two vertices or vertices that enclose no area or are not finite, and
+/// with `NonPlanarPolygon` when a vertex lies further than
+/// `PLANE_THICKNESS_EPS`, scaled by its distance from the origin, off the
+/// best-fit plane.
+pub fn bsp_polygon_by_vertices<V: Vertex>(vertices: Vec<V>) -> Result<BSPPolygon<V>, BspError> {
+    if vertices.is_empty() {
+        return Err(BspError::EmptyInput);
+    }
+
+    let positions: Vec<Vec3> = vertices.iter().map(Vertex::position).collect();
+
+    if !positions.iter().all(|p| p.is_finite()) {
+        return Err(BspError::DegeneratePolygon);
+    }
+
+    let plane = plane_from_polygon(&positions).ok_or(BspError::DegeneratePolygon)?;
+
+    if positions.iter().any(|p| signed_distance(plane, *p).abs() > PLANE_THICKNESS_EPS * (1. + p.len())) {
+        return Err(BspError::NonPlanarPolygon);
+    }
+
+    return Ok(BSPPolygon { plane, vertices, data: () });
+}
+
+impl<V: Vertex, T> BSPPolygon<V, T> {
+    pub fn positions(&self) -> Vec<Vec3> {
+        return self.vertices.iter().map(Vertex::position).collect();
+    }
+
+    /// The same polygon carrying `data` instead.
+    pub fn with_data<U>(self, data: U) -> BSPPolygon<V, U> {
+        return BSPPolygon { plane: self.plane, vertices: self.vertices, data };
+    }
+
+    /// The same as `polygon_area`.
+    pub fn area(&self) -> Scalar {
+        return polygon_area(self);
+    }
+
+    /// The average of the vertex positions. This is not the area-weighted
+    /// centroid: vertices bunched along one side pull it toward that side.
+    /// NaN for a polygon without vertices.
+    pub fn centroid(&self) -> Vec3 {
+        return Vec3::sum(self.vertices.iter().map(Vertex::position)).scale(1. / self.vertices.len() as Scalar);
+    }
+
+    /// Splits the polygon into a fan of triangles around its first vertex,
+    /// keeping the winding. Only correct for convex polygons, which is all
+    /// the build produces.
+    pub fn triangulate(&self) -> Vec<[V; 3]> {
+        let v0 = match self.vertices.first() {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+
+        return self.vertices[1..].windows(2).map(|edge| [v0.clone(), edge[0].clone(), edge[1].clone()]).collect();
+    }
+
+    /// Ear-clipping triangulation for simple polygons that may be concave,
+    /// done in 2D coordinates within the polygon's plane. Keeps the winding.
+    /// If no ear can be found, as with self-intersecting input, the rest is
+    /// fanned.
+    pub fn triangulate_concave(&self) -> Vec<[V; 3]> {
+        let (u, v) = perpendicular_basis(self.plane.n);
+        let flat: Vec<(Scalar, Scalar)> = self.vertices.iter().map(|p| (p.position().dot(u), p.position().dot(v))).collect();
+        let corner = |i: usize| self.vertices[i].clone();
+
+        let cross = |o: usize, a: usize, b: usize| {
+            let (ox, oy) = flat[o];
+            return (flat[a].0 - ox) * (flat[b].1 - oy) - (flat[a].1 - oy) * (flat[b].0 - ox);
+        };
+
+        let mut remaining: Vec<usize> = (0..self.vertices.len()).collect();
+        let mut triangles: Vec<[V; 3]> = Vec::new();
+
+        while remaining.len() > 3 {
+            let n = remaining.len();
+
+            let ear = (0..n).find(|&i| {
+                let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+
+                if cross(a, b, c) <= 0. {
+                    return false;
+                }
+
+                return remaining.iter().all(|&p| {
+                    return flat[p] == flat[a] || flat[p] == flat[b] || flat[p] == flat[c]
+                        || cross(a, b, p) < 0. || cross(b, c, p) < 0. || cross(c, a, p) < 0.;
+                });
+            });
+
+            let i = match ear {
+                Some(i) => i,
+                None => break,
+            };
+
+            let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+            triangles.push([corner(a), corner(b), corner(c)]);
+            remaining.remove(i);
+        }
+
+        for k in 1..remaining.len().saturating_sub(1) {
+            triangles.push([corner(remaining[0]), corner(remaining[k]), corner(remaining[k + 1])]);
+        }
+
+        return triangles;
+    }
+}
+
+/// Projects `p` onto the polygon's plane and keeps the projection if it lies
+/// inside, otherwise takes the nearest point on the boundary. The polygon
+/// must be convex, as every polygon the build produces is.
+pub fn closest_point_on_polygon<V: Vertex, T>(polygon: &BSPPolygon<V, T>, p: Vec3) -> Vec3 {
+    let projected = project_point_onto_plane(polygon.plane, p);
+    let positions = polygon.positions();
+    let n = positions.len();
+
+    let inside = (0..n).all(|i| {
+        let a = positions[i];
+        let b = positions[(i + 1) % n];
+
+        return (b - a).cross(projected - a).dot(polygon.plane.n) >= 0.;
+    });
+
+    if inside {
+        return projected;
+    }
+
+    return (0..n)
+        .map(|i| closest_point_on_segment(positions[i], positions[(i + 1) % n], p))
+        .min_by(|a, b| a.dist_squared(p).total_cmp(&b.dist_squared(p)))
+        .unwrap_or(projected);
+}
+
+/// How far along the edge from `front` to `back` it meets the plane, worked
+/// out as `intersect_segment_plane` does. Rounding can push that just outside
+/// the segment on nearly parallel edges, so it falls back to interpolating by
+/// the endpoints' distances, which always lands on the edge.
+fn edge_crossing(front: Vec3, back: Vec3, plane: BSPPlane) -> Scalar {
+    let denom = plane.n.dot(back - front);
+
+    if denom.abs() >= NORMALIZE_EPS {
+        let t = (plane.d - plane.n.dot(front)) / denom;
+
+        if (0.0..=1.0).contains(&t) {
+            return t;
+        }
+    }
+
+    let front_dist = signed_distance(plane, front);
+    let back_dist = signed_distance(plane, back);
+    let t = front_dist / (front_dist - back_dist);
+
+    return if t.is_finite() { t.clamp(0., 1.) } else { 0.5 };
+}
+
+/// New corners are made with `Vertex::interpolate`, so their attributes are
+/// blended with the same `t` as their positions. Both halves get a copy of
+/// the polygon's `data`.
+pub fn split_bsp_polygon<V: Vertex, T: Clone>(splitting_plane: BSPPlane, polygon: &BSPPolygon<V, T>, config: &BuildConfig<V, T>) -> (BSPPolygon<V, T>, BSPPolygon<V, T>) {
+    let plane = splitting_plane;
+    let BSPPolygon { vertices: points, .. } = polygon;
+
+    let side_of = |v: &V| {
+        let p = v.position();
+        return classify_point_to_plane(plane, p, config.eps_at(plane, p));
+    };
+
+    let mut a = &points[points.len() - 1];
+    let mut a_side = side_of(a);
+
+    let mut front_verts: Vec<V> = Vec::new();
+    let mut back_verts: Vec<V> = Vec::new();
+
+    for b in points.iter() {
+        let b_side = side_of(b);
+
+        // Crossings are always computed from the front point to the back
+        // point so that an edge shared by two polygons splits identically.
+        let crossing = if a_side == PointPlaneSide::FRONT && b_side == PointPlaneSide::BACK {
+            Some(a.interpolate(b, edge_crossing(a.position(), b.position(), plane)))
+        } else if a_side == PointPlaneSide::BACK && b_side == PointPlaneSide::FRONT {
+            Some(b.interpolate(a, edge_crossing(b.position(), a.position(), plane)))
+        } else {
+            None
+        };
+
+        if let Some(int) = crossing {
+            front_verts.push(int.clone());
+            back_verts.push(int);
+        }
+
+        // Coplanar vertices lie on the boundary of both halves.
+        if b_side != PointPlaneSide::BACK {
+            front_verts.push(b.clone());
+        }
+
+        if b_side != PointPlaneSide::FRONT {
+            back_verts.push(b.clone());
+        }
+
+        a = b;
+        a_side = b_side;
+    }
+
+    return ( BSPPolygon {
+        plane: polygon.plane,
+        vertices: front_verts,
+        data: polygon.data.clone()
+    }, BSPPolygon {
+        plane: polygon.plane,
+        vertices: back_verts,
+        data: polygon.data.clone()
+    });
+}
+
+pub fn flip_bsp_polygon<V, T>(polygon: &mut BSPPolygon<V, T>) {
+    polygon.vertices.reverse();
+    polygon.plane = polygon.plane.flip();
+}
+
+/// Moves the polygon by `m`. A transform that mirrors also reverses the
+/// winding, so the vertices still run counter-clockwise around the plane.
+pub fn transform_bsp_polygon<V: Vertex, T>(polygon: &mut BSPPolygon<V, T>, m: &Mat4) {
+    polygon.plane = polygon.plane.transformed(m);
+
+    for v in polygon.vertices.iter_mut() {
+        *v = v.transform(m);
+    }
+
+    if m.determinant() < 0. {
+        polygon.vertices.reverse();
+    }
+}
+
+/// The axis-aligned box around every vertex of `polygons`, as `(min, max)`, or
+/// `None` if there are no vertices.
+pub(crate) fn polygon_bounds<'a, V: Vertex + 'a, T: 'a>(polygons: impl Iterator<Item = &'a BSPPolygon<V, T>>) -> Option<(Vec3, Vec3)> {
+    let mut positions = polygons.flat_map(|polygon| polygon.vertices.iter().map(Vertex::position));
+    let first = positions.next()?;
+
+    return Some(positions.fold((first, first), |(min, max), p| (min.min(p), max.max(p))));
+}
+
+/// Largest angle, in radians, between the normals of two polygons that
+/// `merge_coplanar_polygons` will join. It also bounds how far a boundary
+/// vertex may bend before it counts as a corner rather than collinear.
+pub const MERGE_ANGLE_EPS: Scalar = 1e-4;
+
+/// Repeatedly joins pairs of same-facing coplanar polygons that share an
+/// edge, as long as the joined polygon stays convex, and drops the vertices
+/// left in the middle of straight edges. Meant for undoing triangulation, as
+/// in STL files. The pairwise search is quadratic in the polygon count.
+pub fn merge_coplanar_polygons(polygons: Vec<BSPPolygon>) -> Vec<BSPPolygon> {
+    let mut polygons = polygons;
+    let mut i = 0;
+
+    while i < polygons.len() {
+        let mut j = i + 1;
+
+        while j < polygons.len() {
+            match merge_polygon_pair(&polygons[i], &polygons[j]) {
+                Some(merged) => {
+                    polygons[i] = merged;
+                    polygons.swap_remove(j);
+                    j = i + 1;
+                }
+                None => j += 1,
+            }
+        }
+
+        i += 1;
+    }
+
+    return polygons;
+}
+
+/// Joins `a` and `b` across an edge that `a` runs one way and `b` the other.
+fn merge_polygon_pair(a: &BSPPolygon, b: &BSPPolygon) -> Option<BSPPolygon> {
+    if a.plane.n.dot(b.plane.n) < MERGE_ANGLE_EPS.cos() {
+        return None;
+    }
+
+    let (n, m) = (a.vertices.len(), b.vertices.len());
+
+    for i in 0..n {
+        let p = a.vertices[i];
+        let q = a.vertices[(i + 1) % n];
+
+        let j = match (0..m).find(|&j| b.vertices[j] == q && b.vertices[(j + 1) % m] == p) {
+            Some(j) => j,
+            None => continue,
+        };
+
+        // Walk `a` from q round to p, then `b` from just after p to just
+        // before q.
+        let mut joined: Vec<Vec3> = (0..n).map(|k| a.vertices[(i + 1 + k) % n]).collect();
+        joined.extend((2..m).map(|k| b.vertices[(j + k) % m]));
+
+        let count = joined.len();
+        let mut vertices: Vec<Vec3> = Vec::new();
+
+        for k in 0..count {
+            let prev = joined[(k + count - 1) % count];
+            let v = joined[k];
+            let next = joined[(k + 1) % count];
+
+            let turn = (v - prev).cross(next - v).dot(a.plane.n);
+            let tolerance = MERGE_ANGLE_EPS * (v - prev).len() * (next - v).len();
+
+            if turn < -tolerance {
+                return None;
+            }
+
+            if turn > tolerance {
+                vertices.push(v);
+            }
+        }
+
+        return bsp_polygon_by_vertices(vertices).ok();
+    }
+
+    return None;
+}
+
+/// Snaps vertices closer than `tolerance` to each other onto one shared
+/// position, the first of them seen, using a grid of `tolerance`-sized cells.
+/// Polygons whose vertices collapse onto each other lose the repeats, and
+/// any left without area are removed; planes are refit to the snapped
+/// vertices. Meant for meshes such as STL files whose faces share no
+/// vertices. A `tolerance` that is not positive leaves the polygons as they
+/// are.
+pub fn weld_vertices<T>(polygons: &mut Vec<BSPPolygon<Vec3, T>>, tolerance: Scalar) {
+    if tolerance.is_nan() || tolerance <= 0. {
+        return;
+    }
+
+    let cell_of = |p: Vec3| [p.x, p.y, p.z].map(|c| (c / tolerance).floor() as i64);
+    let mut grid: BTreeMap<[i64; 3], Vec<Vec3>> = BTreeMap::new();
+
+    let mut weld = |p: Vec3| {
+        let [x, y, z] = cell_of(p);
+
+        for cell in (-1..=1).flat_map(|i| (-1..=1).flat_map(move |j| (-1..=1).map(move |k| [x + i, y + j, z + k]))) {
+            let near = grid.get(&cell).and_then(|points| points.iter().find(|q| q.dist(p) <= tolerance));
+
+            if let Some(q) = near {
+                return *q;
+            }
+        }
+
+        grid.entry([x, y, z]).or_default().push(p);
+
+        return p;
+    };
+
+    polygons.retain_mut(|polygon| {
+        let mut vertices: Vec<Vec3> = Vec::new();
+
+        for v in polygon.vertices.iter() {
+            let p = weld(*v);
+
+            if vertices.last() != Some(&p) {
+                vertices.push(p);
+            }
+        }
+
+        while vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+
+        return match plane_from_polygon(&vertices) {
+            Some(plane) => {
+                polygon.plane = plane;
+                polygon.vertices = vertices;
+                true
+            }
+            None => false,
+        };
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+    use alloc::vec;
+
+    use super::*;
+    use super::super::scalar::PI;
+    use super::super::shapes::bsp_cube_faces;
+
+    fn square(min: Vec3, size: Scalar) -> BSPPolygon {
+        return bsp_polygon_by_vertices(vec![
+            min,
+            min + Vec3::UNIT_X * size,
+            min + Vec3 { x: size, y: size, z: 0. },
+            min + Vec3::UNIT_Y * size,
+        ]).unwrap();
+    }
+
+    #[test]
+    fn split_through_a_vertex() {
+        let polygon = square(Vec3::ZERO, 2.);
+        let plane = BSPPlane { n: Vec3 { x: 1., y: -2., z: 0. }.normalized(), d: 0. };
+        let (front, back) = split_bsp_polygon(plane, &polygon, &BuildConfig::default());
+
+        assert_eq!(front.vertices.len(), 3);
+        assert_eq!(back.vertices.len(), 4);
+        assert!((front.area() - 1.).abs() < 1e-5);
+        assert!((back.area() - 3.).abs() < 1e-5);
+        assert_eq!(front.vertices.iter().filter(|v| **v == Vec3::ZERO).count(), 1);
+        assert_eq!(back.vertices.iter().filter(|v| **v == Vec3::ZERO).count(), 1);
+        assert!(front.vertices.iter().all(|v| signed_distance(plane, *v) > -1e-5));
+        assert!(back.vertices.iter().all(|v| signed_distance(plane, *v) < 1e-5));
+    }
+
+    #[test]
+    fn too_few_vertices_are_rejected() {
+        assert_eq!(bsp_polygon_by_vertices(Vec::<Vec3>::new()).err(), Some(BspError::EmptyInput));
+        assert_eq!(bsp_polygon_by_vertices(vec![Vec3::ONE]).err(), Some(BspError::DegeneratePolygon));
+        assert_eq!(bsp_polygon_by_vertices(vec![Vec3::ZERO, Vec3::ONE]).err(), Some(BspError::DegeneratePolygon));
+        assert_eq!(bsp_polygon_by_vertices(vec![Vec3::ONE, Vec3::ONE]).err(), Some(BspError::DegeneratePolygon));
+    }
+
+    fn triangle_normal(t: &[Vec3; 3]) -> Vec3 {
+        return (t[1] - t[0]).cross(t[2] - t[0]) * 0.5;
+    }
+
+    #[test]
+    fn triangulate_convex_polygons() {
+        let quad = square(Vec3::ZERO, 1.);
+        let pentagon = bsp_polygon_by_vertices((0..5).map(|i| {
+            let (sin, cos) = (2. * PI * i as Scalar / 5.).sin_cos();
+            return Vec3 { x: cos, y: sin, z: 0. };
+        }).collect()).unwrap();
+
+        for (polygon, count) in [(&quad, 2), (&pentagon, 3)] {
+            let triangles = polygon.triangulate();
+            let area: Scalar = triangles.iter().map(|t| triangle_normal(t).len()).sum();
+
+            assert_eq!(triangles.len(), count);
+            assert!((area - polygon.area()).abs() < 1e-5);
+            assert!(triangles.iter().all(|t| triangle_normal(t).dot(polygon.plane.n) > 0.));
+        }
+    }
+
+    #[test]
+    fn ear_clip_an_l_shape() {
+        let corners = [(0., 0.), (2., 0.), (2., 1.), (1., 1.), (1., 2.), (0., 2.)];
+        let l_shape = bsp_polygon_by_vertices(corners.iter().map(|&(x, y)| Vec3 { x, y, z: 0. }).collect()).unwrap();
+        let triangles = l_shape.triangulate_concave();
+        let area: Scalar = triangles.iter().map(|t| triangle_normal(t).len()).sum();
+        let in_l = |p: Vec3| p.x > 0. && p.y > 0. && (p.x < 1. || p.y < 1.) && p.x < 2. && p.y < 2.;
+
+        assert_eq!(triangles.len(), 4);
+        assert!((area - 3.).abs() < 1e-5);
+        assert!(triangles.iter().all(|t| triangle_normal(t).z > 0.));
+        assert!(triangles.iter().all(|t| in_l((t[0] + t[1] + t[2]) * (1. / 3.))));
+    }
+
+    #[test]
+    fn collinear_triangle_is_degenerate() {
+        let collinear = vec![Vec3::ZERO, Vec3::ONE, Vec3::ONE * 2.];
+        let bent = vec![Vec3::ZERO, Vec3::UNIT_X, Vec3 { x: 1., y: 1., z: 0.5 }, Vec3::UNIT_Y];
+
+        assert_eq!(bsp_polygon_by_vertices(collinear).err(), Some(BspError::DegeneratePolygon));
+        assert_eq!(bsp_polygon_by_vertices(bent).err(), Some(BspError::NonPlanarPolygon));
+    }
+
+    #[test]
+    fn split_along_a_near_parallel_edge() {
+        let s = 10. * PLANE_THICKNESS_EPS;
+        let polygon = bsp_polygon_by_vertices(vec![
+            Vec3 { x: -1., y: 0., z: -s },
+            Vec3 { x: 1., y: 0., z: s },
+            Vec3 { x: 0., y: 1., z: s },
+        ]).unwrap();
+        let plane = BSPPlane { n: Vec3::UNIT_Z, d: 0. };
+        let (front, back) = split_bsp_polygon(plane, &polygon, &BuildConfig::default());
+
+        assert_eq!(front.vertices.len(), 4);
+        assert_eq!(back.vertices.len(), 3);
+        assert!(front.vertices.iter().chain(back.vertices.iter()).all(|v| v.is_finite() && v.x.abs() <= 1. && v.z.abs() <= s));
+        assert!((front.area() + back.area() - polygon.area()).abs() < 1e-4);
+    }
+
+    #[derive(Clone)]
+    struct UvVertex {
+        position: Vec3,
+        uv: (Scalar, Scalar),
+    }
+
+    impl Vertex for UvVertex {
+        fn position(&self) -> Vec3 {
+            return self.position;
+        }
+
+        fn interpolate(&self, other: &UvVertex, t: Scalar) -> UvVertex {
+            return UvVertex {
+                position: self.position.lerp(other.position, t),
+                uv: (self.uv.0 + (other.uv.0 - self.uv.0) * t, self.uv.1 + (other.uv.1 - self.uv.1) * t),
+            };
+        }
+
+        fn transform(&self, m: &Mat4) -> UvVertex {
+            return UvVertex { position: m.mul_point(self.position), uv: self.uv };
+        }
+    }
+
+    #[test]
+    fn split_interpolates_uvs() {
+        let corner = |x: Scalar, y: Scalar| UvVertex { position: Vec3 { x, y, z: 0. }, uv: (x / 4., y / 2.) };
+        let polygon = bsp_polygon_by_vertices(vec![corner(0., 0.), corner(4., 0.), corner(4., 2.), corner(0., 2.)]).unwrap();
+        let plane = BSPPlane { n: Vec3::UNIT_X, d: 1. };
+        let (front, back) = split_bsp_polygon(plane, &polygon, &BuildConfig::default());
+
+        let new_corners: Vec<&UvVertex> = front.vertices.iter().filter(|v| (v.position.x - 1.).abs() < 1e-5).collect();
+
+        assert_eq!(new_corners.len(), 2);
+        assert!(new_corners.iter().all(|v| (v.uv.0 - 0.25).abs() < 1e-5 && (v.uv.1 - v.position.y / 2.).abs() < 1e-5));
+        assert!(back.vertices.iter().all(|v| v.position.x <= 1. + 1e-5));
+    }
+
+    #[test]
+    fn welding_a_triangle_soup_cube() {
+        let mut polygons: Vec<BSPPolygon> = Vec::new();
+
+        for (i, face) in bsp_cube_faces(Vec3::ZERO, Vec3::ONE).iter().enumerate() {
+            let jitter = Vec3 { x: 1e-4, y: -1e-4, z: 1e-4 } * (i % 3) as Scalar;
+
+            for [a, b, c] in face.triangulate() {
+                polygons.push(bsp_polygon_by_vertices(vec![a + jitter, b + jitter, c + jitter]).unwrap());
+            }
+        }
+
+        let unique = |polygons: &Vec<BSPPolygon>| polygons.iter().flat_map(|p| p.vertices.iter().map(|v| v.to_bits_key())).collect::<BTreeSet<_>>().len();
+        assert!(unique(&polygons) > 8);
+
+        weld_vertices(&mut polygons, 1e-3);
+
+        assert_eq!(polygons.len(), 12);
+        assert_eq!(unique(&polygons), 8);
+    }
+
+    #[test]
+    fn area_of_a_unit_square_and_a_right_triangle() {
+        let triangle = bsp_polygon_by_vertices(vec![Vec3::ZERO, Vec3::UNIT_X, Vec3::UNIT_Y]).unwrap();
+
+        assert!((square(Vec3::ZERO, 1.).area() - 1.).abs() < 1e-6);
+        assert!((triangle.area() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn centroid_of_a_centered_square() {
+        let polygon = square(Vec3 { x: -1., y: -1., z: 0. }, 2.);
+
+        assert!(polygon.centroid().approx_eq(Vec3::ZERO, 1e-6));
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    #[test]
+    fn vertices_need_not_be_thread_safe() {
+        use alloc::rc::Rc;
+
+        #[derive(Clone)]
+        struct NamedVertex {
+            position: Vec3,
+            name: Rc<str>,
+        }
+
+        impl Vertex for NamedVertex {
+            fn position(&self) -> Vec3 {
+                return self.position;
+            }
+
+            fn interpolate(&self, other: &NamedVertex, t: Scalar) -> NamedVertex {
+                return NamedVertex { position: self.position.lerp(other.position, t), name: self.name.clone() };
+            }
+
+            fn transform(&self, m: &Mat4) -> NamedVertex {
+                return NamedVertex { position: m.mul_point(self.position), name: self.name.clone() };
+            }
+        }
+
+        let name: Rc<str> = Rc::from("corner");
+        let corner = |x: Scalar, y: Scalar| NamedVertex { position: Vec3 { x, y, z: 0. }, name: name.clone() };
+        let polygon = bsp_polygon_by_vertices(vec![corner(0., 0.), corner(2., 0.), corner(2., 2.), corner(0., 2.)]).unwrap();
+        let (front, back) = split_bsp_polygon(BSPPlane { n: Vec3::UNIT_X, d: 1. }, &polygon, &BuildConfig::default());
+
+        assert_eq!(front.vertices.len() + back.vertices.len(), 8);
+        assert!(front.vertices.iter().chain(back.vertices.iter()).all(|v| Rc::ptr_eq(&v.name, &name)));
+    }
+}
@@ -0,0 +1,205 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::BspError;
+use super::math::{perpendicular_basis, Scalar, Vec3};
+#[cfg(not(any(feature = "std", test)))]
+use super::math::ScalarMath;
+use super::polygon::{bsp_polygon_by_vertices, BSPPolygon};
+use super::scalar::PI;
+
+/// The faces come in the order -x, +x, -y, +y, -z, +z, each wound to face
+/// outward.
+pub fn bsp_cube_faces(center: Vec3, radius: Vec3) -> Vec<BSPPolygon> {
+    let verts = [
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 2, 3, 1],
+        [4, 5, 7, 6]
+    ];
+
+    return verts.iter().filter_map(|v| {
+        return bsp_polygon_by_vertices(v.iter().map(|i| {
+            return Vec3 {
+                x: center.x + radius.x * (2. * if i & 1 != 0 { 1. } else { 0. } - 1.),
+                y: center.y + radius.y * (2. * if i & 2 != 0 { 1. } else { 0. } - 1.),
+                z: center.z + radius.z * (2. * if i & 4 != 0 { 1. } else { 0. } - 1.),
+            };
+        }).collect()).ok();
+    }).collect();
+}
+
+/// A UV sphere with `slices` segments around the polar (z) axis and `stacks`
+/// bands from pole to pole, `slices * stacks` faces in all. Bands touching a
+/// pole are triangles, the rest are quads, all wound to face outward. Fails
+/// with `DegeneratePolygon` for fewer than 3 slices or 2 stacks, or if any
+/// face comes out degenerate, as it does for a radius too small to give it
+/// any area.
+pub fn bsp_sphere_faces(center: Vec3, radius: Scalar, slices: usize, stacks: usize) -> Result<Vec<BSPPolygon>, BspError> {
+    if slices < 3 || stacks < 2 {
+        return Err(BspError::DegeneratePolygon);
+    }
+
+    let vertex = |i: usize, j: usize| {
+        let phi = 2. * PI * i as Scalar / slices as Scalar;
+        let theta = PI * j as Scalar / stacks as Scalar;
+
+        return center + Vec3 {
+            x: theta.sin() * phi.cos(),
+            y: theta.sin() * phi.sin(),
+            z: theta.cos(),
+        } * radius;
+    };
+
+    let mut polygons: Vec<BSPPolygon> = Vec::new();
+
+    for i in 0..slices {
+        for j in 0..stacks {
+            let next = (i + 1) % slices;
+            let mut vertices = vec![vertex(i, j), vertex(i, j + 1)];
+
+            if j + 1 < stacks {
+                vertices.push(vertex(next, j + 1));
+            }
+
+            if j > 0 {
+                vertices.push(vertex(next, j));
+            }
+
+            polygons.push(bsp_polygon_by_vertices(vertices)?);
+        }
+    }
+
+    return Ok(polygons);
+}
+
+/// `segments` side quads between the two end circles, followed by a triangle
+/// fan over each end cap, all wound to face outward.
+pub fn bsp_cylinder_faces(start: Vec3, end: Vec3, radius: Scalar, segments: usize) -> Vec<BSPPolygon> {
+    let axis = (end - start).normalized();
+    let (u, v) = perpendicular_basis(axis);
+
+    let rim = |i: usize| {
+        let phi = 2. * PI * (i % segments) as Scalar / segments as Scalar;
+
+        return (u * phi.cos() + v * phi.sin()) * radius;
+    };
+
+    let mut sides: Vec<BSPPolygon> = Vec::new();
+    let mut caps: Vec<BSPPolygon> = Vec::new();
+
+    for i in 0..segments {
+        let (r0, r1) = (rim(i), rim(i + 1));
+
+        sides.extend(bsp_polygon_by_vertices(vec![start + r0, start + r1, end + r1, end + r0]));
+        caps.extend(bsp_polygon_by_vertices(vec![start, start + r1, start + r0]));
+        caps.extend(bsp_polygon_by_vertices(vec![end, end + r0, end + r1]));
+    }
+
+    sides.append(&mut caps);
+
+    return sides;
+}
+
+/// `segments` side triangles meeting at the shared `apex`, followed by a
+/// triangle fan over the base cap, all wound to face outward.
+pub fn bsp_cone_faces(base_center: Vec3, apex: Vec3, radius: Scalar, segments: usize) -> Vec<BSPPolygon> {
+    let axis = (apex - base_center).normalized();
+    let (u, v) = perpendicular_basis(axis);
+
+    let rim = |i: usize| {
+        let phi = 2. * PI * (i % segments) as Scalar / segments as Scalar;
+
+        return base_center + (u * phi.cos() + v * phi.sin()) * radius;
+    };
+
+    let mut sides: Vec<BSPPolygon> = Vec::new();
+    let mut caps: Vec<BSPPolygon> = Vec::new();
+
+    for i in 0..segments {
+        let (r0, r1) = (rim(i), rim(i + 1));
+
+        sides.extend(bsp_polygon_by_vertices(vec![r0, r1, apex]));
+        caps.extend(bsp_polygon_by_vertices(vec![base_center, r1, r0]));
+    }
+
+    sides.append(&mut caps);
+
+    return sides;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube_faces_point_outward() {
+        use super::super::plane::bsp_plane_by_three_points;
+
+        let center = Vec3 { x: 1., y: -2., z: 3. };
+        let faces = bsp_cube_faces(center, Vec3 { x: 1., y: 2., z: 3. });
+        let normals = [-Vec3::UNIT_X, Vec3::UNIT_X, -Vec3::UNIT_Y, Vec3::UNIT_Y, -Vec3::UNIT_Z, Vec3::UNIT_Z];
+
+        assert_eq!(faces.len(), 6);
+
+        for (face, normal) in faces.iter().zip(normals) {
+            let v = &face.vertices;
+            let winding = bsp_plane_by_three_points(v[0], v[1], v[2]).unwrap();
+
+            assert!(face.plane.n.approx_eq(normal, 1e-6));
+            assert!(winding.n.approx_eq(normal, 1e-6));
+            assert!((face.centroid() - center).dot(normal) > 0.);
+        }
+    }
+
+    #[test]
+    fn sphere_vertices_lie_on_the_sphere() {
+        let center = Vec3 { x: 1., y: 2., z: -1. };
+        let faces = bsp_sphere_faces(center, 2.5, 12, 6).unwrap();
+
+        assert_eq!(faces.len(), 12 * 6);
+        assert!(faces.iter().flat_map(|f| f.vertices.iter()).all(|v| (v.dist(center) - 2.5).abs() < 1e-5));
+        assert!(faces.iter().all(|f| f.plane.n.dot(f.centroid() - center) > 0.));
+    }
+
+    #[test]
+    fn cylinder_sides_and_flat_caps() {
+        let (start, end) = (Vec3 { x: 0., y: 0., z: -1. }, Vec3 { x: 1., y: 1., z: 2. });
+        let faces = bsp_cylinder_faces(start, end, 0.5, 10);
+        let (sides, caps) = faces.split_at(10);
+
+        assert_eq!(faces.len(), 30);
+        assert!(sides.iter().all(|f| f.vertices.len() == 4));
+
+        for end_point in [start, end] {
+            let cap: Vec<&BSPPolygon> = caps.iter().filter(|f| f.vertices[0] == end_point).collect();
+
+            assert_eq!(cap.len(), 10);
+            assert!(cap.iter().all(|f| f.plane.n.approx_eq(cap[0].plane.n, 1e-5) && (f.plane.d - cap[0].plane.d).abs() < 1e-5));
+        }
+    }
+
+    #[test]
+    fn cone_base_is_flat_and_slopes_are_equal() {
+        let (base, apex) = (Vec3 { x: 1., y: 0., z: 0. }, Vec3 { x: 1., y: 3., z: 1. });
+        let faces = bsp_cone_faces(base, apex, 2., 9);
+        let (sides, caps) = faces.split_at(9);
+        let slant = sides[0].vertices[0].dist(apex);
+
+        assert_eq!(caps.len(), 9);
+        assert!(caps.iter().all(|f| f.plane.n.approx_eq(caps[0].plane.n, 1e-5) && (f.plane.d - caps[0].plane.d).abs() < 1e-5));
+        assert!(caps[0].plane.n.dot(apex - base) < 0.);
+        assert!(sides.iter().all(|f| f.vertices[2] == apex));
+        assert!(sides.iter().all(|f| (f.vertices[0].dist(apex) - slant).abs() < 1e-5 && (f.vertices[0].dist(base) - 2.).abs() < 1e-5));
+    }
+
+    #[test]
+    fn sphere_rejects_too_few_slices_or_stacks_and_an_empty_radius() {
+        assert_eq!(bsp_sphere_faces(Vec3::ZERO, 1., 3, 2).unwrap().len(), 6);
+        assert_eq!(bsp_sphere_faces(Vec3::ZERO, 1., 2, 4).err(), Some(BspError::DegeneratePolygon));
+        assert_eq!(bsp_sphere_faces(Vec3::ZERO, 1., 8, 1).err(), Some(BspError::DegeneratePolygon));
+        assert_eq!(bsp_sphere_faces(Vec3::ZERO, 0., 8, 4).err(), Some(BspError::DegeneratePolygon));
+    }
+}
@@ -0,0 +1,2282 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::BspError;
+use super::math::{Mat4, Scalar, Vec3, NORMALIZE_EPS};
+use super::plane::{classify_point_to_plane, signed_distance, BSPPlane, PointPlaneSide, PLANE_THICKNESS_EPS};
+use super::polygon::{classify_polygon_by_plane, closest_point_on_polygon, flip_bsp_polygon, is_degenerate, polygon_area, polygon_bounds, split_bsp_polygon, transform_bsp_polygon, BSPPolygon, PolygonData, PolygonPlaneSide, Vertex};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "BSPNodeList<V, T>", try_from = "BSPNodeList<V, T>"))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "V: Clone + serde::Serialize, T: Clone + serde::Serialize")))]
+pub struct InnerBSPNode<V = Vec3, T = ()> {
+    pub plane: BSPPlane,
+    pub front: Box<BSPNode<V, T>>,
+    pub back: Box<BSPNode<V, T>>,
+    pub polygons: Vec<BSPPolygon<V, T>>
+}
+
+// The default drop glue recurses once per level, which overflows the stack
+// on the deep trees the iterative build can produce, so children are
+// detached onto a heap stack instead.
+impl<V, T> Drop for InnerBSPNode<V, T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<BSPNode<V, T>> = Vec::new();
+        detach_bsp_children(self, &mut stack);
+
+        while let Some(mut node) = stack.pop() {
+            if let BSPNode::Node(inner) = &mut node {
+                detach_bsp_children(inner, &mut stack);
+            }
+        }
+    }
+}
+
+fn detach_bsp_children<V, T>(node: &mut InnerBSPNode<V, T>, stack: &mut Vec<BSPNode<V, T>>) {
+    for child in [&mut node.front, &mut node.back] {
+        if let BSPNode::Node(_) = **child {
+            stack.push(core::mem::replace(&mut **child, BSPNode::Leaf(Cell::Outside, Vec::new())));
+        }
+    }
+}
+
+// A derived Clone recurses the same way drop glue does, so both clones copy
+// the tree into flat slots and assemble it bottom-up like the build.
+impl<V: Clone, T: Clone> Clone for InnerBSPNode<V, T> {
+    fn clone(&self) -> InnerBSPNode<V, T> {
+        return InnerBSPNode {
+            plane: self.plane,
+            front: Box::new(clone_bsp_node(&self.front)),
+            back: Box::new(clone_bsp_node(&self.back)),
+            polygons: self.polygons.clone()
+        };
+    }
+}
+
+impl<V: Clone, T: Clone> Clone for BSPNode<V, T> {
+    fn clone(&self) -> BSPNode<V, T> {
+        return clone_bsp_node(self);
+    }
+}
+
+fn clone_bsp_node<V: Clone, T: Clone>(root: &BSPNode<V, T>) -> BSPNode<V, T> {
+    let mut pending: Vec<PendingBSPNode<V, T>> = vec![PendingBSPNode::Leaf(Cell::Outside, Vec::new())];
+    let mut work: Vec<(usize, &BSPNode<V, T>)> = vec![(0, root)];
+
+    while let Some((slot, node)) = work.pop() {
+        match node {
+            BSPNode::Node(inner) => {
+                let front_slot = pending.len();
+                let back_slot = front_slot + 1;
+
+                pending.push(PendingBSPNode::Leaf(Cell::Outside, Vec::new()));
+                pending.push(PendingBSPNode::Leaf(Cell::Outside, Vec::new()));
+                pending[slot] = PendingBSPNode::Node(inner.plane, inner.polygons.clone(), front_slot, back_slot);
+
+                work.push((back_slot, &inner.back));
+                work.push((front_slot, &inner.front));
+            }
+            BSPNode::Leaf(cell, polygons) => {
+                pending[slot] = PendingBSPNode::Leaf(*cell, polygons.clone());
+            }
+        }
+    }
+
+    return assemble_bsp_nodes(pending);
+}
+
+/// Whether the space a leaf covers is inside or outside the solid.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Cell {
+    #[cfg_attr(feature = "serde", serde(rename = "inside"))]
+    Inside,
+    #[cfg_attr(feature = "serde", serde(rename = "outside"))]
+    Outside,
+}
+
+/// Leaves on the back side of their parent's plane are `Inside` and those on
+/// the front side are `Outside`, which is exact for closed meshes with
+/// outward-facing polygons. A leaf only holds polygons when the build stopped
+/// early because of `BuildConfig::max_polygons_per_leaf`; its cell then only
+/// reflects the side of the last split, not the unsplit polygons inside it.
+///
+/// With the `serde` feature a tree serializes as `{"nodes": [...]}`, a flat
+/// list with the root first and every node after its parent. Inner nodes
+/// are `{"node": [plane, polygons, front, back]}` with the indices of their
+/// children, and leaves are `{"leaf": [cell, polygons]}`. Keeping the list
+/// flat lets formats with a nesting limit, such as JSON, load trees of any
+/// depth, and the fixed names keep saved trees readable when the Rust names
+/// change.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "BSPNodeList<V, T>", try_from = "BSPNodeList<V, T>"))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "V: Clone + serde::Serialize, T: Clone + serde::Serialize")))]
+pub enum BSPNode<V = Vec3, T = ()> {
+    Node(InnerBSPNode<V, T>),
+    Leaf(Cell, Vec<BSPPolygon<V, T>>)
+}   
+
+/// The serialized form of a tree, described on `BSPNode`.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BSPNodeList<V, T> {
+    nodes: Vec<PendingBSPNode<V, T>>,
+}
+
+#[cfg(feature = "serde")]
+impl<V, T> From<BSPNode<V, T>> for BSPNodeList<V, T> {
+    fn from(root: BSPNode<V, T>) -> BSPNodeList<V, T> {
+        let mut nodes: Vec<PendingBSPNode<V, T>> = vec![PendingBSPNode::Leaf(Cell::Outside, Vec::new())];
+        let mut work: Vec<(usize, BSPNode<V, T>)> = vec![(0, root)];
+
+        while let Some((slot, node)) = work.pop() {
+            match node {
+                BSPNode::Node(mut inner) => {
+                    let front_slot = nodes.len();
+                    let back_slot = front_slot + 1;
+
+                    nodes.push(PendingBSPNode::Leaf(Cell::Outside, Vec::new()));
+                    nodes.push(PendingBSPNode::Leaf(Cell::Outside, Vec::new()));
+                    nodes[slot] = PendingBSPNode::Node(inner.plane, core::mem::take(&mut inner.polygons), front_slot, back_slot);
+
+                    work.push((back_slot, core::mem::replace(&mut *inner.back, BSPNode::Leaf(Cell::Outside, Vec::new()))));
+                    work.push((front_slot, core::mem::replace(&mut *inner.front, BSPNode::Leaf(Cell::Outside, Vec::new()))));
+                }
+                BSPNode::Leaf(cell, polygons) => {
+                    nodes[slot] = PendingBSPNode::Leaf(cell, polygons);
+                }
+            }
+        }
+
+        return BSPNodeList { nodes };
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<V, T> From<InnerBSPNode<V, T>> for BSPNodeList<V, T> {
+    fn from(inner: InnerBSPNode<V, T>) -> BSPNodeList<V, T> {
+        return BSPNode::Node(inner).into();
+    }
+}
+
+// `assemble_bsp_nodes` takes every child out of its slot once, walking
+// backwards, so a list only makes a tree if each slot after the root is the
+// child of exactly one node before it.
+#[cfg(feature = "serde")]
+impl<V, T> TryFrom<BSPNodeList<V, T>> for BSPNode<V, T> {
+    type Error = BspError;
+
+    fn try_from(list: BSPNodeList<V, T>) -> Result<BSPNode<V, T>, BspError> {
+        if list.nodes.is_empty() {
+            return Err(BspError::EmptyInput);
+        }
+
+        let mut parents: Vec<usize> = vec![0; list.nodes.len()];
+
+        for (slot, node) in list.nodes.iter().enumerate() {
+            if let PendingBSPNode::Node(_, _, front, back) = node {
+                for child in [*front, *back] {
+                    if child <= slot || child >= list.nodes.len() {
+                        return Err(BspError::ParseError(format!("node {} has a child at {}, which is not after it in the list", slot, child)));
+                    }
+
+                    parents[child] += 1;
+                }
+            }
+        }
+
+        if let Some(slot) = (1..parents.len()).find(|slot| parents[*slot] != 1) {
+            return Err(BspError::ParseError(format!("node {} is the child of {} nodes", slot, parents[slot])));
+        }
+
+        return Ok(assemble_bsp_nodes(list.nodes));
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<V, T> TryFrom<BSPNodeList<V, T>> for InnerBSPNode<V, T> {
+    type Error = BspError;
+
+    fn try_from(list: BSPNodeList<V, T>) -> Result<InnerBSPNode<V, T>, BspError> {
+        return match BSPNode::try_from(list)? {
+            BSPNode::Node(inner) => Ok(inner),
+            BSPNode::Leaf(..) => Err(BspError::ParseError(String::from("the root is a leaf, not a node"))),
+        };
+    }
+}
+
+// Polygons are shown as counts and children as summaries; printing every
+// vertex of a large tree buries its shape, and printing every level overflows
+// the stack on deep ones.
+impl<V, T> fmt::Debug for BSPNode<V, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            BSPNode::Node(inner) => f.debug_struct("Node")
+                .field("plane", &inner.plane)
+                .field("polygons", &inner.polygons.len())
+                .field("front", &BSPNodeSummary(&inner.front))
+                .field("back", &BSPNodeSummary(&inner.back))
+                .finish(),
+            BSPNode::Leaf(..) => BSPNodeSummary(self).fmt(f),
+        };
+    }
+}
+
+struct BSPNodeSummary<'a, V, T>(&'a BSPNode<V, T>);
+
+impl<V, T> fmt::Debug for BSPNodeSummary<'_, V, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self.0 {
+            BSPNode::Node(_) => {
+                let stats = bsp_subtree_stats(self.0);
+
+                f.debug_struct("Node")
+                    .field("nodes", &stats.node_count)
+                    .field("leaves", &stats.leaf_count)
+                    .field("polygons", &stats.polygon_count)
+                    .finish()
+            }
+            BSPNode::Leaf(cell, polygons) => f.debug_tuple("Leaf")
+                .field(cell)
+                .field(&polygons.len())
+                .finish(),
+        };
+    }
+}
+
+/// Picks which polygon's plane `build_bsp_node` splits on at each node.
+/// `choose` is only called with a non-empty slice and must return an index
+/// into it. `config` is the configuration of the build, so a strategy that
+/// classifies polygons can use the same tolerance as the split that follows.
+/// Strategies are shared across threads by the parallel build, so a stateful
+/// one like `RoundRobin` builds differently each run there.
+pub trait SplitStrategy<V = Vec3, T = ()>: Sync {
+    fn choose(&self, polygons: &[BSPPolygon<V, T>], config: &BuildConfig<V, T>) -> usize;
+}
+
+/// Always splits on the first polygon's plane.
+pub struct FirstPolygon;
+
+/// Cycles through polygon positions from one node to the next.
+#[derive(Default)]
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+
+/// Favors planes with similar polygon counts on both sides.
+pub struct Balanced;
+
+/// Favors planes that cut through as few polygons as possible.
+pub struct MinSplits;
+
+impl<V: Vertex, T: PolygonData> SplitStrategy<V, T> for FirstPolygon {
+    fn choose(&self, _polygons: &[BSPPolygon<V, T>], _config: &BuildConfig<V, T>) -> usize {
+        return 0;
+    }
+}
+
+impl<V: Vertex, T: PolygonData> SplitStrategy<V, T> for RoundRobin {
+    fn choose(&self, polygons: &[BSPPolygon<V, T>], _config: &BuildConfig<V, T>) -> usize {
+        return self.next.fetch_add(1, Ordering::Relaxed) % polygons.len();
+    }
+}
+
+impl<V: Vertex, T: PolygonData> SplitStrategy<V, T> for Balanced {
+    fn choose(&self, polygons: &[BSPPolygon<V, T>], config: &BuildConfig<V, T>) -> usize {
+        return best_scoring_splitter(polygons, config, 1.);
+    }
+}
+
+impl<V: Vertex, T: PolygonData> SplitStrategy<V, T> for MinSplits {
+    fn choose(&self, polygons: &[BSPPolygon<V, T>], config: &BuildConfig<V, T>) -> usize {
+        return best_scoring_splitter(polygons, config, 8.);
+    }
+}
+
+/// Number of polygons sampled as candidate splitters by the scoring
+/// strategies.
+const SPLIT_CANDIDATES: usize = 16;
+
+/// Tolerances and options for building a tree. The default reproduces the
+/// fixed `PLANE_THICKNESS_EPS` behavior; scenes at very large or very small
+/// scales should pick an `eps` to match or set `relative_eps`.
+pub struct BuildConfig<'a, V = Vec3, T = ()> {
+    pub eps: Scalar,
+    /// Scales `eps` by the magnitude of the point and plane offset being
+    /// compared, so the same tolerance works at any coordinate scale.
+    pub relative_eps: bool,
+    pub strategy: &'a dyn SplitStrategy<V, T>,
+    /// Stop subdividing once a partition has at most this many polygons and
+    /// keep them in a leaf. `None` splits all the way down.
+    pub max_polygons_per_leaf: Option<usize>,
+}
+
+// Derived impls would require `V: Copy` and `T: Copy`, but the config only
+// refers to them through the strategy.
+impl<'a, V, T> Clone for BuildConfig<'a, V, T> {
+    fn clone(&self) -> BuildConfig<'a, V, T> {
+        *self
+    }
+}
+
+impl<V, T> Copy for BuildConfig<'_, V, T> {}
+
+impl<V, T> BuildConfig<'_, V, T> {
+    pub fn eps_at(&self, plane: BSPPlane, p: Vec3) -> Scalar {
+        if self.relative_eps {
+            return self.eps * (1. + plane.d.abs() + p.len());
+        }
+
+        return self.eps;
+    }
+}
+
+impl<'a, V: Vertex, T: PolygonData> Default for BuildConfig<'a, V, T> {
+    fn default() -> BuildConfig<'a, V, T> {
+        return BuildConfig {
+            eps: PLANE_THICKNESS_EPS,
+            relative_eps: false,
+            strategy: &FirstPolygon,
+            max_polygons_per_leaf: None,
+        };
+    }
+}
+
+/// Chained setters over a `BuildConfig`. A fresh builder builds exactly what
+/// `from_polygons` does, and `build` checks the polygons the same way.
+///
+/// ```
+/// use bsp::prelude::*;
+///
+/// let faces = bsp_cube_faces(Vec3::ZERO, Vec3::ONE);
+/// let tree: BSPNode = BSPTreeBuilder::new().epsilon(1e-5).build(faces).unwrap();
+/// assert!(tree.point_in_solid(Vec3::ZERO));
+/// ```
+pub struct BSPTreeBuilder<'a, V = Vec3, T = ()> {
+    config: BuildConfig<'a, V, T>,
+}
+
+impl<'a, V, T> Clone for BSPTreeBuilder<'a, V, T> {
+    fn clone(&self) -> BSPTreeBuilder<'a, V, T> {
+        *self
+    }
+}
+
+impl<V, T> Copy for BSPTreeBuilder<'_, V, T> {}
+
+impl<'a, V: Vertex, T: PolygonData> BSPTreeBuilder<'a, V, T> {
+    pub fn new() -> BSPTreeBuilder<'a, V, T> {
+        return BSPTreeBuilder { config: BuildConfig::default() };
+    }
+
+    pub fn epsilon(self, eps: Scalar) -> BSPTreeBuilder<'a, V, T> {
+        return BSPTreeBuilder { config: BuildConfig { eps, ..self.config } };
+    }
+
+    pub fn relative_epsilon(self, relative_eps: bool) -> BSPTreeBuilder<'a, V, T> {
+        return BSPTreeBuilder { config: BuildConfig { relative_eps, ..self.config } };
+    }
+
+    pub fn strategy(self, strategy: &'a dyn SplitStrategy<V, T>) -> BSPTreeBuilder<'a, V, T> {
+        return BSPTreeBuilder { config: BuildConfig { strategy, ..self.config } };
+    }
+
+    pub fn max_leaf_polygons(self, max: usize) -> BSPTreeBuilder<'a, V, T> {
+        return BSPTreeBuilder { config: BuildConfig { max_polygons_per_leaf: Some(max), ..self.config } };
+    }
+
+    pub fn config(&self) -> BuildConfig<'a, V, T> {
+        return self.config;
+    }
+
+    pub fn build(&self, polygons: Vec<BSPPolygon<V, T>>) -> Result<BSPNode<V, T>, BspError> {
+        check_polygons(&polygons, &self.config)?;
+
+        return Ok(build_bsp_node_with_config(polygons, &self.config));
+    }
+
+    /// Like `build`, also returning the tree's stats with `split_count`
+    /// filled in, which only the build knows.
+    pub fn build_with_stats(&self, polygons: Vec<BSPPolygon<V, T>>) -> Result<(BSPNode<V, T>, TreeStats), BspError> {
+        check_polygons(&polygons, &self.config)?;
+
+        let (tree, split_count) = build_bsp_tree(polygons, &self.config);
+        let stats = TreeStats { split_count, ..tree.stats() };
+
+        return Ok((tree, stats));
+    }
+}
+
+impl<'a, V: Vertex, T: PolygonData> Default for BSPTreeBuilder<'a, V, T> {
+    fn default() -> BSPTreeBuilder<'a, V, T> {
+        return BSPTreeBuilder::new();
+    }
+}
+
+/// Builds a tree from a polygon soup with the default configuration. An empty
+/// list produces a single `Outside` leaf. Fails with `DegeneratePolygon` if
+/// any polygon is degenerate at the default `eps` or has coordinates that are
+/// not finite.
+///
+/// ```
+/// use bsp::prelude::*;
+///
+/// let tree = from_polygons(bsp_cube_faces(Vec3::ZERO, Vec3::ONE)).unwrap();
+/// assert!(tree.point_in_solid(Vec3::ZERO));
+/// ```
+pub fn from_polygons<V: Vertex, T: PolygonData>(polygons: Vec<BSPPolygon<V, T>>) -> Result<BSPNode<V, T>, BspError> {
+    return from_polygons_with_strategy(polygons, &FirstPolygon);
+}
+
+pub fn from_polygons_with_strategy<V: Vertex, T: PolygonData>(polygons: Vec<BSPPolygon<V, T>>, strategy: &dyn SplitStrategy<V, T>) -> Result<BSPNode<V, T>, BspError> {
+    return BSPTreeBuilder::new().strategy(strategy).build(polygons);
+}
+
+fn check_polygons<V: Vertex, T: PolygonData>(polygons: &[BSPPolygon<V, T>], config: &BuildConfig<V, T>) -> Result<(), BspError> {
+    for polygon in polygons.iter() {
+        if is_degenerate(polygon, config.eps) || !polygon.vertices.iter().all(|v| v.position().is_finite()) {
+            return Err(BspError::DegeneratePolygon);
+        }
+    }
+
+    return Ok(());
+}
+
+/// Builds a tree without checking the polygons first, for input that is known
+/// to be clean, such as the output of another tree.
+pub fn build_bsp_node<V: Vertex, T: PolygonData>(polygons: Vec<BSPPolygon<V, T>>) -> BSPNode<V, T> {
+    return build_bsp_node_with_config(polygons, &BuildConfig::default());
+}
+
+/// Samples up to `SPLIT_CANDIDATES` evenly spaced polygons and returns the
+/// one whose plane minimizes `|front - back| + split_weight * spanning`,
+/// classifying at the tolerance of `config`.
+fn best_scoring_splitter<V: Vertex, T: PolygonData>(polygons: &[BSPPolygon<V, T>], config: &BuildConfig<V, T>, split_weight: Scalar) -> usize {
+    let step = polygons.len().div_ceil(SPLIT_CANDIDATES);
+    let mut best = 0;
+    let mut best_score = Scalar::INFINITY;
+
+    for candidate in (0..polygons.len()).step_by(step) {
+        let plane = polygons[candidate].plane;
+        let mut front_count = 0;
+        let mut back_count = 0;
+        let mut spanning_count = 0;
+
+        for polygon in polygons.iter() {
+            match classify_polygon_by_plane(plane, polygon, config) {
+                PolygonPlaneSide::FRONT => front_count += 1,
+                PolygonPlaneSide::BACK => back_count += 1,
+                PolygonPlaneSide::SPANNING => spanning_count += 1,
+                PolygonPlaneSide::COPLANAR => {},
+            }
+        }
+
+        let score = (front_count as Scalar - back_count as Scalar).abs()
+            + split_weight * spanning_count as Scalar;
+
+        if score < best_score {
+            best = candidate;
+            best_score = score;
+        }
+    }
+
+    return best;
+}
+
+/// One level of the build: the splitting plane, the polygons that stay at the
+/// node, the polygons handed to each child, and how many polygons were split
+/// to get them.
+struct BSPPartition<V, T> {
+    plane: BSPPlane,
+    polygons: Vec<BSPPolygon<V, T>>,
+    front: Vec<BSPPolygon<V, T>>,
+    back: Vec<BSPPolygon<V, T>>,
+    split_count: usize,
+}
+
+/// Pushes `polygon` onto the side of `plane` it lies on, splitting it if it
+/// spans the plane and dropping any degenerate half. Returns whether it was
+/// split.
+fn sort_polygon_by_plane<V: Vertex, T: PolygonData>(plane: BSPPlane, polygon: BSPPolygon<V, T>, config: &BuildConfig<V, T>,
+                         front: &mut Vec<BSPPolygon<V, T>>, back: &mut Vec<BSPPolygon<V, T>>) -> bool {
+    match classify_polygon_by_plane(plane, &polygon, config) {
+        PolygonPlaneSide::BACK => back.push(polygon),
+        PolygonPlaneSide::FRONT => front.push(polygon),
+        // Coplanar polygons facing the same way as the plane bound the same
+        // half-space, so they go front; opposite ones go back.
+        PolygonPlaneSide::COPLANAR => {
+            if polygon.plane.n.dot(plane.n) > 0. {
+                front.push(polygon);
+            } else {
+                back.push(polygon);
+            }
+        },
+        PolygonPlaneSide::SPANNING => {
+            let (front_poly, back_poly) = split_bsp_polygon( plane, &polygon, config );
+
+            if !is_degenerate(&back_poly, config.eps) {
+                back.push(back_poly);
+            }
+
+            if !is_degenerate(&front_poly, config.eps) {
+                front.push(front_poly);
+            }
+
+            return true;
+        },
+    }
+
+    return false;
+}
+
+/// Partitions a non-empty polygon list around the plane picked by the
+/// configured strategy.
+fn partition_bsp_polygons<V: Vertex, T: PolygonData>(polygons: Vec<BSPPolygon<V, T>>, config: &BuildConfig<V, T>) -> BSPPartition<V, T> {
+    let mut front: Vec<BSPPolygon<V, T>> = Vec::new();
+    let mut back: Vec<BSPPolygon<V, T>> = Vec::new();
+    let mut coplanar: Vec<BSPPolygon<V, T>> = Vec::new();
+    let splitter = config.strategy.choose(&polygons, config);
+    let count = polygons.len();
+
+    let split_plane: BSPPlane = polygons[splitter].plane;
+    let mut split_count = 0;
+
+    // The splitter always stays at this node, even if rounding would classify
+    // it off its own plane, so every level consumes at least one polygon.
+    for (i, polygon) in polygons.into_iter().enumerate() {
+        if i == splitter {
+            coplanar.push(polygon);
+            continue;
+        }
+
+        if sort_polygon_by_plane(split_plane, polygon, config, &mut front, &mut back) {
+            split_count += 1;
+        }
+    }
+
+    // Neither side can hold more pieces than there were other polygons, so
+    // this only trips if splitting misbehaves; keep everything here rather
+    // than recurse forever.
+    if front.len() >= count || back.len() >= count {
+        coplanar.append(&mut front);
+        coplanar.append(&mut back);
+    }
+
+    return BSPPartition { plane: split_plane, polygons: coplanar, front, back, split_count };
+}
+
+/// Shape of a built tree. `node_count` counts inner nodes only, and
+/// `max_depth` is the number of inner nodes on the longest root-to-leaf path.
+/// `split_count` is the number of polygons cut in two during the build. The
+/// tree does not record it, so `BSPNode::stats` reports 0 and only
+/// `BSPTreeBuilder::build_with_stats` fills it in. `branch_count` counts the
+/// inner nodes with polygons on both sides. A closed convex solid builds into
+/// a single chain with no branches, so a low ratio of branches to nodes in a
+/// larger scene means a lopsided tree.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct TreeStats {
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub max_depth: usize,
+    pub polygon_count: usize,
+    pub split_count: usize,
+    pub branch_count: usize,
+}
+
+fn bsp_subtree_stats<V, T>(root: &BSPNode<V, T>) -> TreeStats {
+    let is_empty = |node: &BSPNode<V, T>| matches!(node, BSPNode::Leaf(_, polygons) if polygons.is_empty());
+    let mut stats = TreeStats::default();
+    let mut stack: Vec<(&BSPNode<V, T>, usize)> = vec![(root, 0)];
+
+    while let Some((node, depth)) = stack.pop() {
+        stats.max_depth = stats.max_depth.max(depth);
+
+        match node {
+            BSPNode::Node(inner) => {
+                stats.node_count += 1;
+                stats.polygon_count += inner.polygons.len();
+
+                if !is_empty(&inner.front) && !is_empty(&inner.back) {
+                    stats.branch_count += 1;
+                }
+
+                stack.push((&inner.front, depth + 1));
+                stack.push((&inner.back, depth + 1));
+            }
+            BSPNode::Leaf(_, polygons) => {
+                stats.leaf_count += 1;
+                stats.polygon_count += polygons.len();
+            }
+        }
+    }
+
+    return stats;
+}
+
+/// A node whose children have been assigned slots but not built yet.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum PendingBSPNode<V, T> {
+    #[cfg_attr(feature = "serde", serde(rename = "node"))]
+    Node(BSPPlane, Vec<BSPPolygon<V, T>>, usize, usize),
+    #[cfg_attr(feature = "serde", serde(rename = "leaf"))]
+    Leaf(Cell, Vec<BSPPolygon<V, T>>),
+}
+
+/// Partitions at least this large build their two subtrees in parallel when
+/// the `rayon` feature is enabled.
+#[cfg(feature = "rayon")]
+const PARALLEL_BUILD_THRESHOLD: usize = 256;
+
+pub fn build_bsp_node_with_config<V: Vertex, T: PolygonData>(polygons: Vec<BSPPolygon<V, T>>, config: &BuildConfig<V, T>) -> BSPNode<V, T> {
+    return build_bsp_tree(polygons, config).0;
+}
+
+/// Builds a whole tree, returning it with the number of polygons split.
+fn build_bsp_tree<V: Vertex, T: PolygonData>(polygons: Vec<BSPPolygon<V, T>>, config: &BuildConfig<V, T>) -> (BSPNode<V, T>, usize) {
+    #[cfg(feature = "rayon")]
+    return build_bsp_subtree_parallel(polygons, config, Cell::Outside);
+
+    #[cfg(not(feature = "rayon"))]
+    return build_bsp_subtree(polygons, config, Cell::Outside);
+}
+
+/// Splits a large partition and builds its two halves with `rayon::join`
+/// while both are large, and with the serial builder otherwise. Forking only
+/// on balanced splits keeps the recursion shallow, since a lopsided split
+/// sends its big side to the serial builder instead of one level deeper. The
+/// result is the same tree the serial build produces.
+#[cfg(feature = "rayon")]
+fn build_bsp_subtree_parallel<V: Vertex, T: PolygonData>(polygons: Vec<BSPPolygon<V, T>>, config: &BuildConfig<V, T>, cell: Cell) -> (BSPNode<V, T>, usize) {
+    if polygons.len() < PARALLEL_BUILD_THRESHOLD
+        || config.max_polygons_per_leaf.is_some_and(|max| polygons.len() <= max) {
+        return build_bsp_subtree(polygons, config, cell);
+    }
+
+    let partition = partition_bsp_polygons(polygons, config);
+
+    let ((front, front_splits), (back, back_splits)) = if partition.front.len() >= PARALLEL_BUILD_THRESHOLD && partition.back.len() >= PARALLEL_BUILD_THRESHOLD {
+        rayon::join(
+            || build_bsp_subtree_parallel(partition.front, config, Cell::Outside),
+            || build_bsp_subtree_parallel(partition.back, config, Cell::Inside),
+        )
+    } else {
+        (build_bsp_subtree(partition.front, config, Cell::Outside), build_bsp_subtree(partition.back, config, Cell::Inside))
+    };
+
+    let node = BSPNode::Node(InnerBSPNode {
+        plane: partition.plane,
+        front: Box::new(front),
+        back: Box::new(back),
+        polygons: partition.polygons
+    });
+
+    return (node, partition.split_count + front_splits + back_splits);
+}
+
+/// Builds the tree with an explicit work stack rather than recursion, so very
+/// deep trees are limited by the heap instead of the call stack. Nodes are
+/// laid out in the same depth-first, front-first order as a recursive build
+/// would visit them, then assembled bottom-up. `cell` is the cell of the root
+/// if it ends up a leaf. Returns the subtree with the number of polygons
+/// split while building it.
+fn build_bsp_subtree<V: Vertex, T: PolygonData>(polygons: Vec<BSPPolygon<V, T>>, config: &BuildConfig<V, T>, cell: Cell) -> (BSPNode<V, T>, usize) {
+    let mut pending: Vec<PendingBSPNode<V, T>> = vec![PendingBSPNode::Leaf(cell, Vec::new())];
+    let mut work: Vec<(usize, Vec<BSPPolygon<V, T>>)> = vec![(0, polygons)];
+    let mut split_count = 0;
+
+    while let Some((slot, polygons)) = work.pop() {
+        if polygons.is_empty() {
+            continue;
+        }
+
+        if config.max_polygons_per_leaf.is_some_and(|max| polygons.len() <= max) {
+            if let PendingBSPNode::Leaf(_, leaf_polygons) = &mut pending[slot] {
+                *leaf_polygons = polygons;
+            }
+
+            continue;
+        }
+
+        let partition = partition_bsp_polygons(polygons, config);
+        let front_slot = pending.len();
+        split_count += partition.split_count;
+        let back_slot = front_slot + 1;
+
+        pending.push(PendingBSPNode::Leaf(Cell::Outside, Vec::new()));
+        pending.push(PendingBSPNode::Leaf(Cell::Inside, Vec::new()));
+        pending[slot] = PendingBSPNode::Node(partition.plane, partition.polygons, front_slot, back_slot);
+
+        work.push((back_slot, partition.back));
+        work.push((front_slot, partition.front));
+    }
+
+    return (assemble_bsp_nodes(pending), split_count);
+}
+
+/// Gives every leaf under `root` the same cell.
+fn fill_bsp_leaf_cells<V, T>(root: &mut BSPNode<V, T>, cell: Cell) {
+    let mut stack: Vec<&mut BSPNode<V, T>> = vec![root];
+
+    while let Some(node) = stack.pop() {
+        match node {
+            BSPNode::Node(inner) => {
+                stack.push(&mut inner.front);
+                stack.push(&mut inner.back);
+            }
+            BSPNode::Leaf(leaf_cell, _) => *leaf_cell = cell,
+        }
+    }
+}
+
+fn assemble_bsp_nodes<V, T>(pending: Vec<PendingBSPNode<V, T>>) -> BSPNode<V, T> {
+    // Children always sit after their parent, so walking backwards builds
+    // every subtree before the node that owns it.
+    let mut built: Vec<Option<BSPNode<V, T>>> = pending.iter().map(|_| None).collect();
+
+    for (slot, node) in pending.into_iter().enumerate().rev() {
+        built[slot] = Some(match node {
+            PendingBSPNode::Node(plane, polygons, front, back) => BSPNode::Node(InnerBSPNode {
+                plane,
+                front: Box::new(built[front].take().unwrap()),
+                back: Box::new(built[back].take().unwrap()),
+                polygons
+            }),
+            PendingBSPNode::Leaf(cell, polygons) => BSPNode::Leaf(cell, polygons),
+        });
+    }
+
+    return built[0].take().unwrap();
+}
+
+/// Where a ray crosses the surface of the solid: `point` is `origin + dir * t`,
+/// and `plane` is the surface crossed, facing out of the solid.
+#[derive(Debug, Copy, Clone)]
+pub struct RayHit {
+    pub t: Scalar,
+    pub point: Vec3,
+    pub plane: BSPPlane,
+}
+
+/// A stretch of a ray that lies in one leaf: it starts at `t`, crossing
+/// `plane` unless it is the first stretch, and lies in `cell`.
+struct RaySegment {
+    t: Scalar,
+    cell: Cell,
+    plane: Option<BSPPlane>,
+}
+
+/// Lazily yields every polygon in a tree, node polygons and leaf polygons
+/// alike, in the same order as `BSPNode::all_polygons`.
+pub struct BSPPolygonIter<'a, V = Vec3, T = ()> {
+    stack: Vec<&'a BSPNode<V, T>>,
+    current: core::slice::Iter<'a, BSPPolygon<V, T>>,
+}
+
+impl<'a, V, T> Iterator for BSPPolygonIter<'a, V, T> {
+    type Item = &'a BSPPolygon<V, T>;
+
+    fn next(&mut self) -> Option<&'a BSPPolygon<V, T>> {
+        loop {
+            if let Some(polygon) = self.current.next() {
+                return Some(polygon);
+            }
+
+            match self.stack.pop()? {
+                BSPNode::Node(inner) => {
+                    self.current = inner.polygons.iter();
+
+                    self.stack.push(&inner.back);
+                    self.stack.push(&inner.front);
+                }
+                BSPNode::Leaf(_, polygons) => {
+                    self.current = polygons.iter();
+                }
+            }
+        }
+    }
+}
+
+/// Callbacks for `BSPNode::accept`, which walks the tree depth-first, visiting
+/// each node before its front subtree and the front before the back.
+pub trait BSPVisitor<V = Vec3, T = ()> {
+    fn visit_node(&mut self, plane: BSPPlane, polygons: &[BSPPolygon<V, T>]);
+    fn visit_leaf(&mut self);
+}
+
+impl<V: Vertex, T: PolygonData> BSPNode<V, T> {
+    /// Swaps solid and empty space: every plane and polygon is flipped, every
+    /// leaf changes cell, and the children of every node trade places.
+    pub fn invert(&mut self) {
+        let mut stack: Vec<&mut BSPNode<V, T>> = vec![self];
+
+        while let Some(node) = stack.pop() {
+            match node {
+                BSPNode::Node(inner) => {
+                    inner.plane = inner.plane.flip();
+
+                    for polygon in inner.polygons.iter_mut() {
+                        flip_bsp_polygon(polygon);
+                    }
+
+                    core::mem::swap(&mut inner.front, &mut inner.back);
+
+                    stack.push(&mut inner.front);
+                    stack.push(&mut inner.back);
+                }
+                BSPNode::Leaf(cell, polygons) => {
+                    for polygon in polygons.iter_mut() {
+                        flip_bsp_polygon(polygon);
+                    }
+
+                    *cell = match cell {
+                        Cell::Inside => Cell::Outside,
+                        Cell::Outside => Cell::Inside,
+                    };
+                }
+            }
+        }
+    }
+
+    pub fn inverted(self) -> BSPNode<V, T> {
+        let mut node = self;
+        node.invert();
+
+        return node;
+    }
+
+    /// Reflects the solid through `plane`. Reflecting the vertices alone would
+    /// reverse every polygon's winding and leave the solid inside out, so the
+    /// windings are flipped back and the normals still face outward.
+    pub fn mirror_across(&mut self, plane: BSPPlane) {
+        self.transform(&Mat4::reflection(plane));
+    }
+
+    /// Moves the whole solid by `m` in place, transforming every polygon and
+    /// plane while keeping the shape of the tree, so nothing is rebuilt.
+    /// Panics if `m` is singular, since a flattened solid has no planes.
+    pub fn transform(&mut self, m: &Mat4) {
+        assert!(m.determinant() != 0., "cannot transform a tree by a singular matrix");
+
+        let mut stack: Vec<&mut BSPNode<V, T>> = vec![self];
+
+        while let Some(node) = stack.pop() {
+            match node {
+                BSPNode::Node(inner) => {
+                    inner.plane = inner.plane.transformed(m);
+
+                    for polygon in inner.polygons.iter_mut() {
+                        transform_bsp_polygon(polygon, m);
+                    }
+
+                    stack.push(&mut inner.front);
+                    stack.push(&mut inner.back);
+                }
+                BSPNode::Leaf(_, polygons) => {
+                    for polygon in polygons.iter_mut() {
+                        transform_bsp_polygon(polygon, m);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes the parts of `polygons` that lie inside this solid, that is,
+    /// that reach an `Inside` leaf. Coplanar polygons follow their facing.
+    pub fn clip_polygons(&self, polygons: Vec<BSPPolygon<V, T>>) -> Vec<BSPPolygon<V, T>> {
+        let config = BuildConfig::default();
+        let mut result: Vec<BSPPolygon<V, T>> = Vec::new();
+        let mut stack = vec![(self, polygons)];
+
+        while let Some((node, polygons)) = stack.pop() {
+            let inner = match node {
+                BSPNode::Node(inner) => inner,
+                BSPNode::Leaf(Cell::Inside, _) => continue,
+                BSPNode::Leaf(Cell::Outside, _) => {
+                    result.extend(polygons);
+                    continue;
+                }
+            };
+
+            let mut front: Vec<BSPPolygon<V, T>> = Vec::new();
+            let mut back: Vec<BSPPolygon<V, T>> = Vec::new();
+
+            for polygon in polygons.into_iter() {
+                sort_polygon_by_plane(inner.plane, polygon, &config, &mut front, &mut back);
+            }
+
+            stack.push((&*inner.back, back));
+            stack.push((&*inner.front, front));
+        }
+
+        return result;
+    }
+
+    /// Clips the polygons stored at every node against `other`, removing the
+    /// parts that lie inside its solid. The planes and shape of this tree are
+    /// left as they are.
+    pub fn clip_to(&mut self, other: &BSPNode<V, T>) {
+        let mut stack: Vec<&mut BSPNode<V, T>> = vec![self];
+
+        while let Some(node) = stack.pop() {
+            match node {
+                BSPNode::Node(inner) => {
+                    inner.polygons = other.clip_polygons(core::mem::take(&mut inner.polygons));
+
+                    stack.push(&mut inner.front);
+                    stack.push(&mut inner.back);
+                }
+                BSPNode::Leaf(_, polygons) => {
+                    *polygons = other.clip_polygons(core::mem::take(polygons));
+                }
+            }
+        }
+    }
+
+    /// Adds a polygon to an existing tree without rebuilding it. The polygon
+    /// is split at every node it spans, and each fragment that reaches a leaf
+    /// turns that leaf into a subtree built from the fragment. The new leaves
+    /// keep the cell of the leaf they replace, so inserting never changes
+    /// which points are inside the solid. Nothing is rebalanced, so many
+    /// inserts can leave the tree deep and lopsided.
+    pub fn insert(&mut self, polygon: BSPPolygon<V, T>) {
+        self.insert_polygons(vec![polygon], &BuildConfig::default());
+    }
+
+    pub fn insert_polygons(&mut self, polygons: Vec<BSPPolygon<V, T>>, config: &BuildConfig<V, T>) {
+        let mut stack = vec![(self, polygons)];
+
+        while let Some((node, polygons)) = stack.pop() {
+            if polygons.is_empty() {
+                continue;
+            }
+
+            match node {
+                BSPNode::Node(inner) => {
+                    let mut front: Vec<BSPPolygon<V, T>> = Vec::new();
+                    let mut back: Vec<BSPPolygon<V, T>> = Vec::new();
+
+                    for polygon in polygons.into_iter() {
+                        sort_polygon_by_plane(inner.plane, polygon, config, &mut front, &mut back);
+                    }
+
+                    stack.push((&mut *inner.front, front));
+                    stack.push((&mut *inner.back, back));
+                }
+                BSPNode::Leaf(cell, leaf_polygons) => {
+                    let cell = *cell;
+                    let mut all = core::mem::take(leaf_polygons);
+                    all.extend(polygons);
+
+                    *node = build_bsp_subtree(all, config, cell).0;
+                    fill_bsp_leaf_cells(node, cell);
+                }
+            }
+        }
+    }
+
+    pub fn is_empty_leaf(&self) -> bool {
+        return match self {
+            BSPNode::Node(_) => false,
+            BSPNode::Leaf(_, polygons) => polygons.is_empty(),
+        };
+    }
+
+    pub fn stats(&self) -> TreeStats {
+        return bsp_subtree_stats(self);
+    }
+
+    /// Returns the polygons in painter's order for `viewer`: at every node the
+    /// subtree on the far side of the plane comes first, then the node's own
+    /// polygons, then the near side. Drawing them in this order leaves the
+    /// nearest surfaces on top, which is what blending transparent geometry
+    /// needs. Polygons kept in a leaf by `max_polygons_per_leaf` are not
+    /// sorted among themselves.
+    pub fn polygons_back_to_front(&self, viewer: Vec3) -> Vec<&BSPPolygon<V, T>> {
+        let mut polygons: Vec<&BSPPolygon<V, T>> = Vec::new();
+        self.walk_in_view_order(viewer, false, |node| extend_in_view_order(&mut polygons, node_polygons(node), false));
+
+        return polygons;
+    }
+
+    /// The exact reverse of `polygons_back_to_front`. Use it for opaque
+    /// geometry with a depth buffer or occlusion test, where drawing the
+    /// nearest surfaces first lets later, hidden ones be rejected early.
+    pub fn polygons_front_to_back(&self, viewer: Vec3) -> Vec<&BSPPolygon<V, T>> {
+        let mut polygons: Vec<&BSPPolygon<V, T>> = Vec::new();
+        self.walk_in_view_order(viewer, true, |node| extend_in_view_order(&mut polygons, node_polygons(node), true));
+
+        return polygons;
+    }
+
+    /// The planes of the tree's nodes in the same back-to-front order as
+    /// `polygons_back_to_front`, one entry per node.
+    pub fn render_order(&self, viewer: Vec3) -> Vec<BSPPlane> {
+        let mut planes: Vec<BSPPlane> = Vec::new();
+
+        self.walk_in_view_order(viewer, false, |node| {
+            if let BSPNode::Node(inner) = node {
+                planes.push(inner.plane);
+            }
+        });
+
+        return planes;
+    }
+
+    /// Calls `visit` on every node and leaf in view order: at each node the
+    /// subtree on the far side of the plane (or the near side, with
+    /// `near_first`) is visited first, then the node itself, then the other
+    /// side.
+    fn walk_in_view_order<'a>(&'a self, viewer: Vec3, near_first: bool, mut visit: impl FnMut(&'a BSPNode<V, T>)) {
+        let mut stack: Vec<ViewStep<'a, V, T>> = vec![ViewStep::Subtree(self)];
+
+        while let Some(step) = stack.pop() {
+            let node = match step {
+                ViewStep::Subtree(node) => node,
+                ViewStep::Node(node) => {
+                    visit(node);
+                    continue;
+                }
+            };
+
+            match node {
+                BSPNode::Node(inner) => {
+                    let viewer_in_front = inner.plane.n.dot(viewer) > inner.plane.d;
+
+                    let (first, second) = if viewer_in_front == near_first {
+                        (&inner.front, &inner.back)
+                    } else {
+                        (&inner.back, &inner.front)
+                    };
+
+                    stack.push(ViewStep::Subtree(second));
+                    stack.push(ViewStep::Node(node));
+                    stack.push(ViewStep::Subtree(first));
+                }
+                BSPNode::Leaf(..) => visit(node),
+            }
+        }
+    }
+
+    /// The `(min, max)` corners of the box around every polygon vertex in the
+    /// tree, or `None` if it holds no polygons. This walks the whole tree on
+    /// each call.
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        return polygon_bounds(self.iter_polygons());
+    }
+
+    /// A sphere around every polygon vertex, found with Ritter's algorithm. It
+    /// is usually a little larger than the smallest enclosing sphere.
+    pub fn bounding_sphere(&self) -> Option<(Vec3, Scalar)> {
+        let vertices: Vec<Vec3> = self.iter_polygons().flat_map(|polygon| polygon.vertices.iter().map(Vertex::position)).collect();
+        let farthest_from = |p: Vec3| {
+            return vertices.iter().copied().max_by(|a, b| a.dist_squared(p).total_cmp(&b.dist_squared(p)));
+        };
+
+        let y = farthest_from(*vertices.first()?)?;
+        let z = farthest_from(y)?;
+
+        let mut center = y.midpoint(z);
+        let mut radius = y.dist(z) / 2.;
+
+        for v in vertices.iter() {
+            let dist = v.dist(center);
+
+            if dist > radius {
+                radius = (radius + dist) / 2.;
+                center = *v + (center - *v).with_length(radius);
+            }
+        }
+
+        return Some((center, radius));
+    }
+
+    /// Back-to-front polygons, like `polygons_back_to_front`, skipping any
+    /// subtree or polygon whose bounding box lies entirely behind one of the
+    /// `frustum` planes. The planes face into the frustum. Subtree bounds are
+    /// not stored in the tree, so each call measures them all once, bottom-up.
+    pub fn visible_polygons(&self, viewer: Vec3, frustum: &[BSPPlane; 6]) -> Vec<&BSPPolygon<V, T>> {
+        let nodes = bound_bsp_subtrees(self);
+        let mut polygons: Vec<&BSPPolygon<V, T>> = Vec::new();
+        let mut stack: Vec<VisibleStep<V, T>> = vec![VisibleStep::Subtree(0)];
+
+        let visible = |polygon: &&BSPPolygon<V, T>| {
+            return match polygon_bounds(core::iter::once(*polygon)) {
+                Some((min, max)) => box_in_frustum(min, max, frustum),
+                None => false,
+            };
+        };
+
+        while let Some(step) = stack.pop() {
+            let slot = match step {
+                VisibleStep::Subtree(slot) => slot,
+                VisibleStep::Polygons(node_polygons) => {
+                    polygons.extend(node_polygons.iter().filter(visible));
+                    continue;
+                }
+            };
+
+            match nodes[slot].bounds {
+                Some((min, max)) if box_in_frustum(min, max, frustum) => {}
+                _ => continue,
+            }
+
+            match nodes[slot].node {
+                BSPNode::Node(inner) => {
+                    let (front, back) = nodes[slot].children.unwrap();
+                    let (far, near) = if inner.plane.n.dot(viewer) > inner.plane.d {
+                        (back, front)
+                    } else {
+                        (front, back)
+                    };
+
+                    stack.push(VisibleStep::Subtree(near));
+                    stack.push(VisibleStep::Polygons(&inner.polygons));
+                    stack.push(VisibleStep::Subtree(far));
+                }
+                BSPNode::Leaf(_, leaf_polygons) => {
+                    polygons.extend(leaf_polygons.iter().filter(visible));
+                }
+            }
+        }
+
+        return polygons;
+    }
+
+    /// Descends to the leaf containing `p` and reports whether it is solid.
+    /// Points within `PLANE_THICKNESS_EPS` of a plane go to its front, so a
+    /// point on the surface of a solid counts as outside.
+    pub fn point_in_solid(&self, p: Vec3) -> bool {
+        let mut node = self;
+
+        loop {
+            match node {
+                BSPNode::Node(inner) => {
+                    node = match classify_point_to_plane(inner.plane, p, PLANE_THICKNESS_EPS) {
+                        PointPlaneSide::BACK => &inner.back,
+                        PointPlaneSide::FRONT | PointPlaneSide::COPLANAR => &inner.front,
+                    };
+                }
+                BSPNode::Leaf(cell, _) => return *cell == Cell::Inside,
+            }
+        }
+    }
+
+    /// The first place where the ray from `origin` along `dir` passes from
+    /// empty space into the solid, for `t > 0`. `t` is measured in multiples
+    /// of `dir`. A ray starting inside the solid only hits once it has left
+    /// and gone back in.
+    pub fn raycast(&self, origin: Vec3, dir: Vec3) -> Option<RayHit> {
+        return self.first_entry(origin, dir, Scalar::INFINITY);
+    }
+
+    /// Like `raycast` for the segment from `a` to `b`, with `t` running from 0
+    /// at `a` to 1 at `b`. A segment that only touches the surface at `b`
+    /// does not hit.
+    pub fn segment_hits_solid(&self, a: Vec3, b: Vec3) -> Option<RayHit> {
+        return self.first_entry(a, b - a, 1.);
+    }
+
+    /// True when no part of the segment from `from` to `to` passes through
+    /// the solid. A segment with an endpoint inside the solid is blocked,
+    /// while one that only touches or slides along the surface is not.
+    pub fn line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+        let mut segments: Vec<RaySegment> = Vec::new();
+        self.collect_ray_segments(from, to - from, 0., 1., None, &mut segments);
+
+        return segments.iter().all(|segment| segment.cell == Cell::Outside);
+    }
+
+    fn first_entry(&self, origin: Vec3, dir: Vec3, t_max: Scalar) -> Option<RayHit> {
+        let mut segments: Vec<RaySegment> = Vec::new();
+        self.collect_ray_segments(origin, dir, 0., t_max, None, &mut segments);
+
+        return segments.windows(2).find_map(|pair| {
+            return match (pair[0].cell, pair[1].cell, pair[1].plane) {
+                (Cell::Outside, Cell::Inside, Some(plane)) => Some(RayHit {
+                    t: pair[1].t,
+                    point: origin + dir * pair[1].t,
+                    plane
+                }),
+                _ => None,
+            };
+        });
+    }
+
+    /// Every place the ray passes into or out of the solid for `t > 0`,
+    /// sorted by `t`, so entries and exits alternate.
+    pub fn raycast_all(&self, origin: Vec3, dir: Vec3) -> Vec<RayHit> {
+        let mut segments: Vec<RaySegment> = Vec::new();
+        self.collect_ray_segments(origin, dir, 0., Scalar::INFINITY, None, &mut segments);
+
+        return segments.windows(2).filter_map(|pair| {
+            let plane = match (pair[0].cell, pair[1].cell, pair[1].plane) {
+                (Cell::Outside, Cell::Inside, Some(plane)) => plane,
+                (Cell::Inside, Cell::Outside, Some(plane)) => plane.flip(),
+                _ => return None,
+            };
+
+            return Some(RayHit { t: pair[1].t, point: origin + dir * pair[1].t, plane });
+        }).collect();
+    }
+
+    /// Walks the leaves the ray passes through between `t0` and `t1`, nearest
+    /// first. `plane` is the plane crossed at `t0`, if any.
+    fn collect_ray_segments(&self, origin: Vec3, dir: Vec3, t0: Scalar, t1: Scalar, plane: Option<BSPPlane>, segments: &mut Vec<RaySegment>) {
+        let mut stack = vec![(self, t0, t1, plane)];
+
+        while let Some((node, t0, t1, plane)) = stack.pop() {
+            let inner = match node {
+                BSPNode::Node(inner) => inner,
+                BSPNode::Leaf(cell, _) => {
+                    segments.push(RaySegment { t: t0, cell: *cell, plane });
+                    continue;
+                }
+            };
+
+            let denom = inner.plane.n.dot(dir);
+            let dist = signed_distance(inner.plane, origin);
+            let t = if denom.abs() > NORMALIZE_EPS { -dist / denom } else { Scalar::INFINITY };
+
+            if t <= t0 || t >= t1 {
+                let probe = if t1.is_finite() { (t0 + t1) / 2. } else { t0 + 1. };
+                let child = if dist + denom * probe >= 0. { &inner.front } else { &inner.back };
+
+                stack.push((&**child, t0, t1, plane));
+                continue;
+            }
+
+            let (near, far, surface) = if denom < 0. {
+                (&inner.front, &inner.back, inner.plane)
+            } else {
+                (&inner.back, &inner.front, inner.plane.flip())
+            };
+
+            stack.push((&**far, t, t1, Some(surface)));
+            stack.push((&**near, t0, t, plane));
+        }
+    }
+
+    /// The closest point to `p` on any polygon in the tree, or `None` if the
+    /// tree holds no polygons.
+    pub fn nearest_surface_point(&self, p: Vec3) -> Option<Vec3> {
+        return self.iter_polygons()
+            .map(|polygon| closest_point_on_polygon(polygon, p))
+            .min_by(|a, b| a.dist_squared(p).total_cmp(&b.dist_squared(p)));
+    }
+
+    /// Distance from `p` to the nearest surface, negative inside the solid.
+    /// A tree without polygons is infinitely far from its surface.
+    pub fn signed_distance_to_surface(&self, p: Vec3) -> Scalar {
+        let dist = match self.nearest_surface_point(p) {
+            Some(q) => q.dist(p),
+            None => Scalar::INFINITY,
+        };
+
+        return if self.point_in_solid(p) { -dist } else { dist };
+    }
+
+    /// The enclosed volume, summed from the signed tetrahedra between the
+    /// origin and a fan of triangles over each polygon. Only meaningful when
+    /// the polygons form a closed surface with outward-facing windings.
+    pub fn volume(&self) -> Scalar {
+        let tetrahedra = self.iter_polygons()
+            .flat_map(|polygon| polygon.triangulate())
+            .map(|[a, b, c]| Vec3::triple(a.position(), b.position(), c.position()));
+
+        return tetrahedra.sum::<Scalar>() / 6.;
+    }
+
+    pub fn surface_area(&self) -> Scalar {
+        return self.iter_polygons().map(polygon_area).sum();
+    }
+
+    pub fn accept(&self, visitor: &mut impl BSPVisitor<V, T>) {
+        let mut stack: Vec<&BSPNode<V, T>> = vec![self];
+
+        while let Some(node) = stack.pop() {
+            match node {
+                BSPNode::Node(inner) => {
+                    visitor.visit_node(inner.plane, &inner.polygons);
+
+                    stack.push(&inner.back);
+                    stack.push(&inner.front);
+                }
+                BSPNode::Leaf(..) => {
+                    visitor.visit_leaf();
+                }
+            }
+        }
+    }
+
+    /// Number of inner nodes, matching `TreeStats::node_count`.
+    pub fn node_count(&self) -> usize {
+        return bsp_subtree_stats(self).node_count;
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        return bsp_subtree_stats(self).leaf_count;
+    }
+
+    pub fn iter_polygons(&self) -> BSPPolygonIter<'_, V, T> {
+        return BSPPolygonIter { stack: vec![self], current: [].iter() };
+    }
+
+    /// A Graphviz digraph of the tree. Inner nodes are ellipses labelled with
+    /// their plane and polygon count, leaves are boxes labelled with their
+    /// cell, and edges are labelled `front` or `back`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph bsp {\n");
+        let mut next_id = 1;
+        let mut stack: Vec<(&BSPNode<V, T>, usize)> = vec![(self, 0)];
+
+        while let Some((node, id)) = stack.pop() {
+            match node {
+                BSPNode::Node(inner) => {
+                    dot.push_str(&format!(
+                        "    n{} [label=\"n = {}, d = {}\\n{} polygons\"];\n",
+                        id, inner.plane.n, inner.plane.d, inner.polygons.len()
+                    ));
+
+                    for (child, side) in [(&inner.front, "front"), (&inner.back, "back")] {
+                        dot.push_str(&format!("    n{} -> n{} [label=\"{}\"];\n", id, next_id, side));
+                        stack.push((child, next_id));
+                        next_id += 1;
+                    }
+                }
+                BSPNode::Leaf(cell, polygons) => {
+                    let cell = match cell {
+                        Cell::Inside => "inside",
+                        Cell::Outside => "outside",
+                    };
+
+                    dot.push_str(&format!(
+                        "    n{} [shape=box, label=\"{}\\n{} polygons\"];\n",
+                        id, cell, polygons.len()
+                    ));
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+
+        return dot;
+    }
+
+    pub fn all_polygons(&self) -> Vec<BSPPolygon<V, T>> {
+        let mut polygons: Vec<BSPPolygon<V, T>> = Vec::new();
+        self.collect_polygons(&mut polygons);
+
+        return polygons;
+    }
+
+    pub fn collect_polygons(&self, polygons: &mut Vec<BSPPolygon<V, T>>) {
+        polygons.extend(self.iter_polygons().cloned());
+    }
+}
+
+/// A node with the slots of its children and the box around every polygon in
+/// its subtree.
+struct BoundedBSPNode<'a, V, T> {
+    node: &'a BSPNode<V, T>,
+    children: Option<(usize, usize)>,
+    bounds: Option<(Vec3, Vec3)>
+}
+
+/// A pending step of `walk_in_view_order`: a subtree still to descend into,
+/// or a node to visit between its two subtrees.
+enum ViewStep<'a, V, T> {
+    Subtree(&'a BSPNode<V, T>),
+    Node(&'a BSPNode<V, T>),
+}
+
+enum VisibleStep<'a, V, T> {
+    Subtree(usize),
+    Polygons(&'a [BSPPolygon<V, T>]),
+}
+
+/// Lays the tree out breadth-first, so children always sit after their
+/// parent, then fills in the bounds from the last slot back.
+fn bound_bsp_subtrees<V: Vertex, T>(root: &BSPNode<V, T>) -> Vec<BoundedBSPNode<'_, V, T>> {
+    let mut nodes = vec![BoundedBSPNode { node: root, children: None, bounds: None }];
+    let mut slot = 0;
+
+    while slot < nodes.len() {
+        if let BSPNode::Node(inner) = nodes[slot].node {
+            let front = nodes.len();
+
+            nodes.push(BoundedBSPNode { node: &inner.front, children: None, bounds: None });
+            nodes.push(BoundedBSPNode { node: &inner.back, children: None, bounds: None });
+            nodes[slot].children = Some((front, front + 1));
+        }
+
+        slot += 1;
+    }
+
+    for slot in (0..nodes.len()).rev() {
+        let mut bounds = match nodes[slot].node {
+            BSPNode::Node(inner) => polygon_bounds(inner.polygons.iter()),
+            BSPNode::Leaf(_, polygons) => polygon_bounds(polygons.iter()),
+        };
+
+        if let Some((front, back)) = nodes[slot].children {
+            for child in [front, back] {
+                bounds = union_bounds(bounds, nodes[child].bounds);
+            }
+        }
+
+        nodes[slot].bounds = bounds;
+    }
+
+    return nodes;
+}
+
+fn union_bounds(a: Option<(Vec3, Vec3)>, b: Option<(Vec3, Vec3)>) -> Option<(Vec3, Vec3)> {
+    return match (a, b) {
+        (Some((a_min, a_max)), Some((b_min, b_max))) => Some((a_min.min(b_min), a_max.max(b_max))),
+        (a, None) => a,
+        (None, b) => b,
+    };
+}
+
+/// False only when the box is entirely behind some plane, which can keep a
+/// few boxes that are outside near the frustum's edges.
+fn box_in_frustum(min: Vec3, max: Vec3, frustum: &[BSPPlane; 6]) -> bool {
+    return frustum.iter().all(|plane| {
+        let farthest = Vec3 {
+            x: if plane.n.x >= 0. { max.x } else { min.x },
+            y: if plane.n.y >= 0. { max.y } else { min.y },
+            z: if plane.n.z >= 0. { max.z } else { min.z },
+        };
+
+        return signed_distance(*plane, farthest) >= 0.;
+    });
+}
+
+fn node_polygons<V, T>(node: &BSPNode<V, T>) -> &[BSPPolygon<V, T>] {
+    return match node {
+        BSPNode::Node(inner) => &inner.polygons,
+        BSPNode::Leaf(_, polygons) => polygons,
+    };
+}
+
+/// Appends `source` in stored order, or reversed when traversing near first,
+/// so the two view orders are exact mirrors of each other.
+fn extend_in_view_order<'a, V, T>(polygons: &mut Vec<&'a BSPPolygon<V, T>>, source: &'a [BSPPolygon<V, T>], reversed: bool) {
+    if reversed {
+        polygons.extend(source.iter().rev());
+    } else {
+        polygons.extend(source.iter());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::polygon::bsp_polygon_by_vertices;
+    use super::super::shapes::{bsp_cube_faces, bsp_sphere_faces};
+
+    fn cube(center: Vec3, half: Scalar) -> Vec<BSPPolygon> {
+        return bsp_cube_faces(center, Vec3::ONE * half);
+    }
+
+    // Builds a tree `depth` nodes deep directly, since building one that deep
+    // from polygons takes quadratic time. Node `i` holds a unit square on the
+    // plane `z = i`, and the solid is everything below `z = 0`.
+    fn chain(depth: usize) -> BSPNode {
+        let mut node = BSPNode::Leaf(Cell::Inside, Vec::new());
+
+        for i in 0..depth {
+            let square = cube(Vec3 { x: 0.5, y: 0.5, z: i as Scalar - 0.5 }, 0.5).remove(5);
+
+            node = BSPNode::Node(InnerBSPNode {
+                plane: square.plane,
+                front: Box::new(BSPNode::Leaf(Cell::Outside, Vec::new())),
+                back: Box::new(node),
+                polygons: vec![square]
+            });
+        }
+
+        return node;
+    }
+
+    // Runs `f` on a thread whose stack is far too small to walk a deep tree
+    // recursively, so any recursion left in a traversal aborts the test.
+    #[cfg(feature = "std")]
+    fn on_small_stack(f: impl FnOnce() + Send + 'static) {
+        std::thread::Builder::new().stack_size(64 * 1024).spawn(f).unwrap().join().unwrap();
+    }
+
+    fn same_tree(a: &BSPNode, b: &BSPNode) -> bool {
+        let vertices = |node: &BSPNode| node.iter_polygons().map(|p| p.vertices.clone()).collect::<Vec<_>>();
+
+        return a.to_dot() == b.to_dot() && vertices(a) == vertices(b);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_build_matches_serial() {
+        let mut polygons: Vec<BSPPolygon> = Vec::new();
+
+        for i in 0..3 {
+            for j in 0..3 {
+                let center = Vec3 { x: 3. * i as Scalar, y: 3. * j as Scalar, z: 0. };
+                polygons.extend(bsp_sphere_faces(center, 1., 16, 8).unwrap());
+            }
+        }
+
+        let config = BuildConfig { strategy: &Balanced, ..BuildConfig::default() };
+        let (parallel, parallel_splits) = build_bsp_subtree_parallel(polygons.clone(), &config, Cell::Outside);
+        let (serial, serial_splits) = build_bsp_subtree(polygons, &config, Cell::Outside);
+
+        assert!(parallel.node_count() > 2 * PARALLEL_BUILD_THRESHOLD);
+        assert!(same_tree(&parallel, &serial));
+        assert_eq!(parallel_splits, serial_splits);
+    }
+
+    #[test]
+    fn build_handles_a_lopsided_stack() {
+        let build = || {
+            let polygons: Vec<BSPPolygon> = (0..2000).map(|i| cube(Vec3 { x: 0., y: 0., z: i as Scalar }, 0.25).remove(5)).collect();
+            let tree = from_polygons(polygons).unwrap();
+
+            assert_eq!(tree.stats().max_depth, 2000);
+        };
+
+        #[cfg(feature = "std")]
+        on_small_stack(build);
+        #[cfg(not(feature = "std"))]
+        build();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn queries_handle_a_deep_tree() {
+        on_small_stack(|| {
+            let tree = chain(50_000);
+            let above = Vec3 { x: 0.5, y: 0.5, z: 60_000. };
+            let frustum = [Vec3::UNIT_X, -Vec3::UNIT_X, Vec3::UNIT_Y, -Vec3::UNIT_Y, Vec3::UNIT_Z, -Vec3::UNIT_Z].map(|n| BSPPlane { n, d: -1e6 });
+
+            assert_eq!(tree.polygons_back_to_front(above).len(), 50_000);
+            assert_eq!(tree.polygons_front_to_back(above).len(), 50_000);
+            assert_eq!(tree.visible_polygons(above, &frustum).len(), 50_000);
+            assert_eq!(tree.render_order(above).len(), 50_000);
+            assert_eq!((tree.node_count(), tree.leaf_count()), (50_000, 50_001));
+            assert_eq!(tree.all_polygons().len(), 50_000);
+            assert!((tree.raycast(above, -Vec3::UNIT_Z).unwrap().t - 60_000.).abs() < 1e-2);
+            assert_eq!(tree.raycast_all(above, -Vec3::UNIT_Z).len(), 1);
+            assert!(tree.line_of_sight(above, Vec3 { x: 0.5, y: 0.5, z: 0.5 }));
+            assert!(tree.clip_polygons(cube(Vec3 { x: 0., y: 0., z: -5. }, 1.)).is_empty());
+            assert_eq!(tree.clip_polygons(cube(Vec3 { x: 0., y: 0., z: 25_000.5 }, 0.25)).len(), 6);
+        });
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn edits_handle_a_deep_tree() {
+        on_small_stack(|| {
+            let mut tree = chain(50_000);
+            let other = from_polygons(cube(Vec3 { x: 0.5, y: 0.5, z: 100.5 }, 0.75)).unwrap();
+
+            tree.clip_to(&other);
+            tree.insert(cube(Vec3 { x: 0., y: 0., z: -1. }, 0.5).remove(5));
+
+            assert_eq!(tree.iter_polygons().count(), 49_999);
+            assert_eq!(tree.stats().max_depth, 50_001);
+            assert!(tree.point_in_solid(Vec3 { x: 0., y: 0., z: -2. }));
+        });
+    }
+
+    #[test]
+    fn cube_stats() {
+        let stats = from_polygons(cube(Vec3::ZERO, 1.)).unwrap().stats();
+
+        assert_eq!(stats, TreeStats { node_count: 6, leaf_count: 7, max_depth: 6, polygon_count: 6, split_count: 0, branch_count: 0 });
+    }
+
+    #[test]
+    fn stats_count_branches() {
+        let mut polygons = cube(Vec3 { x: -5., y: 0., z: 0. }, 1.);
+        polygons.extend(cube(Vec3 { x: 5., y: 0., z: 0. }, 1.));
+
+        let stats = from_polygons(polygons).unwrap().stats();
+
+        assert_eq!(stats.polygon_count, 12);
+        assert!(stats.branch_count >= 1);
+    }
+
+    #[test]
+    fn clone_is_independent() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let mut copy = tree.clone();
+
+        copy.invert();
+
+        assert!(tree.point_in_solid(Vec3::ZERO));
+        assert!(!copy.point_in_solid(Vec3::ZERO));
+        assert_eq!(copy.stats().node_count, tree.stats().node_count);
+    }
+
+    #[test]
+    fn clone_handles_a_deep_tree() {
+        let tree = chain(50_000);
+
+        assert_eq!(tree.clone().stats().max_depth, 50_000);
+    }
+
+    #[test]
+    fn debug_summarizes_the_tree() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let text = format!("{:?}", tree);
+
+        assert!(text.contains("Node"));
+        assert!(text.contains("Leaf"));
+        assert!(format!("{:?}", chain(50_000)).contains("nodes: 49999"));
+    }
+
+    #[test]
+    fn invert_swaps_inside_and_outside() {
+        let mut tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let far = Vec3 { x: 5., y: 0., z: 0. };
+
+        tree.invert();
+
+        assert!(!tree.point_in_solid(Vec3::ZERO));
+        assert!(tree.point_in_solid(far));
+        assert!(tree.iter_polygons().all(|p| p.plane.n.dot(p.centroid()) < 0.));
+    }
+
+    #[test]
+    fn invert_handles_a_deep_tree() {
+        let mut tree = chain(50_000);
+
+        tree.invert();
+
+        assert!(!tree.point_in_solid(Vec3 { x: 0., y: 0., z: -1. }));
+        assert!(tree.point_in_solid(Vec3 { x: 0., y: 0., z: 50_000.5 }));
+    }
+
+    #[test]
+    fn frustum_culls_half_the_scene() {
+        let mut polygons = cube(Vec3 { x: -5., y: 0., z: 0. }, 1.);
+        polygons.extend(cube(Vec3 { x: 5., y: 0., z: 0. }, 1.));
+
+        let tree = from_polygons(polygons).unwrap();
+        let viewer = Vec3 { x: 5., y: 0., z: 8. };
+        let axis = |x: Scalar, y: Scalar, z: Scalar, d: Scalar| BSPPlane { n: Vec3 { x, y, z }, d };
+        let frustum = [axis(1., 0., 0., 0.), axis(-1., 0., 0., -10.), axis(0., 1., 0., -10.), axis(0., -1., 0., -10.), axis(0., 0., 1., -10.), axis(0., 0., -1., -10.)];
+
+        let visible = tree.visible_polygons(viewer, &frustum);
+        let expected: Vec<&BSPPolygon> = tree.polygons_back_to_front(viewer).into_iter().filter(|p| p.centroid().x > 0.).collect();
+
+        assert_eq!(visible.len(), 6);
+        assert!(visible.iter().zip(expected.iter()).all(|(a, b)| core::ptr::eq(*a, *b)));
+        assert_eq!(visible.len(), expected.len());
+    }
+
+    #[test]
+    fn default_builder_matches_from_polygons() {
+        let faces = cube(Vec3::ZERO, 1.);
+        let built = BSPTreeBuilder::new().build(faces.clone()).unwrap();
+
+        assert!(same_tree(&built, &from_polygons(faces).unwrap()));
+    }
+
+    #[test]
+    fn insert_keeps_containment() {
+        let mut tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let inside = Vec3 { x: 0., y: 0., z: 0.5 };
+        let outside = Vec3 { x: 1.5, y: 0., z: -0.5 };
+        let square = bsp_polygon_by_vertices(vec![
+            Vec3 { x: -2., y: -2., z: 0. },
+            Vec3 { x: 2., y: -2., z: 0. },
+            Vec3 { x: 2., y: 2., z: 0. },
+            Vec3 { x: -2., y: 2., z: 0. },
+        ])
+        .unwrap();
+
+        tree.insert(square);
+
+        let fragments: Vec<BSPPolygon> = tree.all_polygons().into_iter().filter(|p| p.vertices.iter().all(|v| v.z.abs() < 1e-4)).collect();
+        let area: Scalar = fragments.iter().map(|p| p.area()).sum();
+
+        assert!(fragments.len() > 1);
+        assert!((area - 16.).abs() < 1e-3);
+        assert!(tree.point_in_solid(inside));
+        assert!(!tree.point_in_solid(outside));
+    }
+
+    #[test]
+    fn large_cube_with_a_matching_epsilon() {
+        let faces = cube(Vec3::ZERO, 1e6);
+        let config = BuildConfig { eps: 1., ..BuildConfig::default() };
+        let mut nudged = faces[0].clone();
+        transform_bsp_polygon(&mut nudged, &Mat4::translation(faces[0].plane.n * 0.5));
+        let tree = BSPTreeBuilder::new().epsilon(1.).build(faces.clone()).unwrap();
+
+        assert_eq!(classify_polygon_by_plane(faces[0].plane, &nudged, &config), PolygonPlaneSide::COPLANAR);
+        assert_eq!(classify_polygon_by_plane(faces[0].plane, &nudged, &BuildConfig::default()), PolygonPlaneSide::FRONT);
+        assert_eq!(tree.stats().node_count, 6);
+        assert!(tree.point_in_solid(Vec3::ONE * 5e5));
+        assert!(!tree.point_in_solid(Vec3 { x: 1.5e6, y: 0., z: 0. }));
+    }
+
+    #[test]
+    fn sliver_halves_are_dropped() {
+        let splitter = bsp_polygon_by_vertices(vec![
+            Vec3 { x: 0., y: -5., z: -5. },
+            Vec3 { x: 0., y: 5., z: -5. },
+            Vec3 { x: 0., y: 5., z: 5. },
+            Vec3 { x: 0., y: -5., z: 5. },
+        ])
+        .unwrap();
+        let triangle = bsp_polygon_by_vertices(vec![
+            Vec3 { x: 3. * PLANE_THICKNESS_EPS, y: 0., z: 0. },
+            Vec3 { x: -1., y: -1., z: 0. },
+            Vec3 { x: -1., y: 1., z: 0. },
+        ])
+        .unwrap();
+
+        let (sliver, rest) = split_bsp_polygon(splitter.plane, &triangle, &BuildConfig::default());
+        let tree = build_bsp_node(vec![splitter, triangle]);
+
+        assert!(is_degenerate(&sliver, PLANE_THICKNESS_EPS));
+        assert!(!is_degenerate(&rest, PLANE_THICKNESS_EPS));
+        assert_eq!(tree.stats().polygon_count, 2);
+
+        match tree {
+            BSPNode::Node(inner) => assert!(inner.front.is_empty_leaf()),
+            BSPNode::Leaf(..) => panic!("expected a split"),
+        }
+    }
+
+    #[test]
+    fn relative_epsilon_at_small_and_large_scales() {
+        let relative = BuildConfig { relative_eps: true, ..BuildConfig::default() };
+
+        for scale in [100. * PLANE_THICKNESS_EPS, 1e6] {
+            let faces = cube(Vec3 { x: 3. * scale, y: 0., z: 0. }, scale);
+            let top = &faces[5];
+            let noise = top.plane.n * (0.1 * PLANE_THICKNESS_EPS * scale);
+            let mut noisy = top.clone();
+            noisy.vertices[0] += noise;
+            noisy.vertices[1] -= noise;
+            let mut lifted = top.clone();
+            transform_bsp_polygon(&mut lifted, &Mat4::translation(top.plane.n * (0.1 * scale)));
+
+            assert_eq!(classify_polygon_by_plane(top.plane, &noisy, &relative), PolygonPlaneSide::COPLANAR);
+            assert_eq!(classify_polygon_by_plane(top.plane, &lifted, &relative), PolygonPlaneSide::FRONT);
+            assert_eq!(classify_polygon_by_plane(top.plane, &faces[4], &relative), PolygonPlaneSide::BACK);
+        }
+    }
+
+    #[test]
+    fn coincident_opposite_cubes_build() {
+        let mut polygons = cube(Vec3::ZERO, 1.);
+        polygons.extend(cube(Vec3::ZERO, 1.).into_iter().map(|mut p| {
+            flip_bsp_polygon(&mut p);
+            return p;
+        }));
+
+        let tree = from_polygons(polygons).unwrap();
+
+        assert_eq!(tree.stats().polygon_count, 12);
+    }
+
+    #[test]
+    fn coplanar_polygons_sort_by_facing() {
+        let faces = cube(Vec3::ZERO, 1.);
+        let top = faces[5].plane;
+        let same = cube(Vec3 { x: 0., y: 0., z: 0.5 }, 0.5).remove(5);
+        let mut opposite = same.clone();
+        flip_bsp_polygon(&mut opposite);
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+
+        sort_polygon_by_plane(top, same, &BuildConfig::default(), &mut front, &mut back);
+        sort_polygon_by_plane(top, opposite, &BuildConfig::default(), &mut front, &mut back);
+
+        assert_eq!(front.len(), 1);
+        assert_eq!(back.len(), 1);
+        assert!(front[0].plane.n.dot(top.n) > 0.);
+        assert!(back[0].plane.n.dot(top.n) < 0.);
+    }
+
+    #[test]
+    fn clip_faces_against_an_overlapping_cube() {
+        let other = from_polygons(cube(Vec3::ONE, 1.)).unwrap();
+        let kept = other.clip_polygons(cube(Vec3::ZERO, 1.));
+        let area: Scalar = kept.iter().map(|p| p.area()).sum();
+
+        assert_eq!(kept.len(), 12);
+        assert!((area - 21.).abs() < 1e-4);
+        assert!(kept.iter().all(|p| !other.point_in_solid(p.centroid())));
+    }
+
+    #[test]
+    fn clip_to_removes_polygons_inside_the_other_solid() {
+        let mut tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let other = from_polygons(cube(Vec3::ONE, 1.)).unwrap();
+
+        tree.clip_to(&other);
+
+        let polygons = tree.all_polygons();
+        let area: Scalar = polygons.iter().map(|p| p.area()).sum();
+
+        assert!(polygons.iter().all(|p| !other.point_in_solid(p.centroid())));
+        assert!((area - 21.).abs() < 1e-4);
+        assert!(tree.point_in_solid(Vec3 { x: -0.5, y: -0.5, z: -0.5 }));
+    }
+
+    #[test]
+    fn all_polygons_of_a_cube() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let polygons = tree.all_polygons();
+
+        assert_eq!(polygons.len(), 6);
+        assert_eq!(polygons.iter().map(|p| p.vertices.len()).sum::<usize>(), 24);
+        assert_eq!(polygons.len(), tree.stats().polygon_count);
+    }
+
+    #[test]
+    fn split_minimizing_strategy_on_a_grid_of_cubes() {
+        let mut polygons: Vec<BSPPolygon> = Vec::new();
+
+        for i in 0..27 {
+            let center = Vec3 { x: (i % 3) as Scalar, y: (i / 3 % 3) as Scalar, z: (i / 9) as Scalar } * 1.5;
+            polygons.extend(cube(center, 1.));
+        }
+
+        let first = from_polygons_with_strategy(polygons.clone(), &FirstPolygon).unwrap().stats();
+        let min_splits = from_polygons_with_strategy(polygons.clone(), &MinSplits).unwrap().stats();
+        let balanced = from_polygons_with_strategy(polygons, &Balanced).unwrap().stats();
+
+        assert!(min_splits.node_count < first.node_count);
+        assert!(balanced.max_depth < first.max_depth);
+        assert!(first.polygon_count > 27 * 6);
+    }
+
+    #[test]
+    fn custom_strategy_changes_the_tree() {
+        struct LastPolygon;
+
+        impl SplitStrategy for LastPolygon {
+            fn choose(&self, polygons: &[BSPPolygon], _config: &BuildConfig) -> usize {
+                return polygons.len() - 1;
+            }
+        }
+
+        let last = from_polygons_with_strategy(cube(Vec3::ZERO, 1.), &LastPolygon).unwrap();
+        let first = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+
+        assert!(!same_tree(&last, &first));
+        assert_eq!(last.stats(), first.stats());
+
+        match last {
+            BSPNode::Node(inner) => assert!(inner.plane.n.approx_eq(Vec3::UNIT_Z, 1e-6)),
+            BSPNode::Leaf(..) => panic!("expected a split"),
+        }
+    }
+
+    #[test]
+    fn cube_interior_leaf_is_inside() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let mut node = &tree;
+
+        while let BSPNode::Node(inner) = node {
+            assert!(matches!(*inner.front, BSPNode::Leaf(Cell::Outside, _)));
+            node = &inner.back;
+        }
+
+        assert!(matches!(node, BSPNode::Leaf(Cell::Inside, _)));
+    }
+
+    #[test]
+    fn leaves_respect_the_polygon_limit() {
+        let polygons = bsp_sphere_faces(Vec3::ZERO, 1., 12, 6).unwrap();
+        let tree = BSPTreeBuilder::new().max_leaf_polygons(4).build(polygons).unwrap();
+        let mut leaf_sizes: Vec<usize> = Vec::new();
+        let mut stack = vec![&tree];
+
+        while let Some(node) = stack.pop() {
+            match node {
+                BSPNode::Node(inner) => stack.extend([&*inner.front, &*inner.back]),
+                BSPNode::Leaf(_, polygons) => leaf_sizes.push(polygons.len()),
+            }
+        }
+
+        assert!(leaf_sizes.iter().all(|n| *n <= 4));
+        assert!(leaf_sizes.iter().any(|n| *n > 0));
+        assert_eq!(tree.stats().polygon_count, 72);
+    }
+
+    fn layers() -> BSPNode {
+        let polygons: Vec<BSPPolygon> = [1., 0., 2.].iter().map(|z| cube(Vec3 { x: 0., y: 0., z: *z }, 0.5).remove(5)).collect();
+
+        return from_polygons(polygons).unwrap();
+    }
+
+    #[test]
+    fn back_to_front_order() {
+        let tree = layers();
+        let heights = |polygons: Vec<&BSPPolygon>| polygons.iter().map(|p| p.centroid().z).collect::<Vec<Scalar>>();
+
+        assert_eq!(heights(tree.polygons_back_to_front(Vec3 { x: 0., y: 0., z: 10. })), [0.5, 1.5, 2.5]);
+        assert_eq!(heights(tree.polygons_back_to_front(Vec3 { x: 0., y: 0., z: -10. })), [2.5, 1.5, 0.5]);
+    }
+
+    #[test]
+    fn front_to_back_reverses_back_to_front() {
+        let mut polygons = bsp_sphere_faces(Vec3::ZERO, 1., 8, 4).unwrap();
+        polygons.extend(cube(Vec3 { x: 0.5, y: 0.5, z: 0. }, 1.));
+        polygons.extend(cube(Vec3 { x: -2., y: 1., z: 0.3 }, 0.7));
+
+        let tree = from_polygons(polygons).unwrap();
+        let viewer = Vec3 { x: 3., y: -4., z: 2. };
+        let mut back_to_front = tree.polygons_back_to_front(viewer);
+        back_to_front.reverse();
+        let front_to_back = tree.polygons_front_to_back(viewer);
+
+        assert_eq!(front_to_back.len(), back_to_front.len());
+        assert!(front_to_back.iter().zip(back_to_front.iter()).all(|(a, b)| core::ptr::eq(*a, *b)));
+    }
+
+    #[test]
+    fn visitor_counts_match_stats() {
+        #[derive(Default)]
+        struct Counter {
+            nodes: usize,
+            leaves: usize,
+            polygons: usize,
+        }
+
+        impl BSPVisitor for Counter {
+            fn visit_node(&mut self, _plane: BSPPlane, polygons: &[BSPPolygon]) {
+                self.nodes += 1;
+                self.polygons += polygons.len();
+            }
+
+            fn visit_leaf(&mut self) {
+                self.leaves += 1;
+            }
+        }
+
+        let mut polygons = cube(Vec3::ZERO, 1.);
+        polygons.extend(cube(Vec3::ONE, 1.));
+        let tree = from_polygons(polygons).unwrap();
+        let stats = tree.stats();
+        let mut counter = Counter::default();
+
+        tree.accept(&mut counter);
+
+        assert_eq!(counter.leaves, stats.leaf_count);
+        assert_eq!(counter.nodes, stats.node_count);
+        assert_eq!(counter.polygons, stats.polygon_count);
+    }
+
+    #[test]
+    fn render_order_of_the_demo_cube() {
+        let tree = from_polygons(cube(Vec3::ZERO, 5.)).unwrap();
+        let viewer = Vec3 { x: 10., y: 10., z: 0. };
+        let normals: Vec<Vec3> = tree.render_order(viewer).iter().map(|plane| plane.n).collect();
+        let polygon_normals: Vec<Vec3> = tree.polygons_back_to_front(viewer).iter().map(|p| p.plane.n).collect();
+
+        let expected = [-Vec3::UNIT_X, -Vec3::UNIT_Y, -Vec3::UNIT_Z, Vec3::UNIT_Z, Vec3::UNIT_Y, Vec3::UNIT_X];
+
+        assert_eq!(normals.len(), expected.len());
+        assert!(normals.iter().zip(expected).all(|(n, e)| n.approx_eq(e, 1e-6)));
+        assert_eq!(normals, polygon_normals);
+    }
+
+    #[test]
+    fn node_and_leaf_counts() {
+        let cube_tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let single = from_polygons(vec![cube(Vec3::ZERO, 1.).remove(0)]).unwrap();
+
+        assert_eq!(cube_tree.node_count(), 6);
+        assert_eq!(cube_tree.leaf_count(), 7);
+        assert_eq!(single.node_count(), 1);
+        assert_eq!(single.leaf_count(), 2);
+        assert_eq!(from_polygons(Vec::<BSPPolygon>::new()).unwrap().leaf_count(), 1);
+    }
+
+    #[test]
+    fn iterator_yields_every_polygon() {
+        let mut polygons = bsp_sphere_faces(Vec3::ZERO, 1., 8, 4).unwrap();
+        polygons.extend(cube(Vec3::ONE, 1.));
+        let tree = from_polygons(polygons.clone()).unwrap();
+        let limited = BSPTreeBuilder::new().max_leaf_polygons(3).build(polygons).unwrap();
+
+        assert_eq!(tree.iter_polygons().count(), tree.all_polygons().len());
+        assert_eq!(limited.iter_polygons().count(), limited.all_polygons().len());
+        assert!(tree.iter_polygons().count() >= 38);
+    }
+
+    #[test]
+    fn point_in_solid_inside_outside_and_on_a_face() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+
+        assert!(tree.point_in_solid(Vec3 { x: 0.5, y: -0.5, z: 0.9 }));
+        assert!(!tree.point_in_solid(Vec3 { x: 0.5, y: -0.5, z: 1.1 }));
+        assert!(!tree.point_in_solid(Vec3 { x: -3., y: 0., z: 0. }));
+        assert!(!tree.point_in_solid(Vec3 { x: 0.5, y: -0.5, z: 1. }));
+        assert!(!tree.point_in_solid(Vec3 { x: -1., y: 0., z: 0. }));
+    }
+
+    #[test]
+    fn raycast_hits_the_near_face() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let hit = tree.raycast(Vec3 { x: -5., y: 0.2, z: 0.3 }, Vec3 { x: 2., y: 0., z: 0. }).unwrap();
+
+        assert!((hit.t - 2.).abs() < 1e-5);
+        assert!(hit.point.approx_eq(Vec3 { x: -1., y: 0.2, z: 0.3 }, 1e-5));
+        assert!(hit.plane.n.approx_eq(-Vec3::UNIT_X, 1e-6));
+        assert!(tree.raycast(Vec3 { x: -5., y: 2., z: 0. }, Vec3::UNIT_X).is_none());
+        assert!(tree.raycast(Vec3 { x: -5., y: 0., z: 0. }, -Vec3::UNIT_X).is_none());
+    }
+
+    #[test]
+    fn raycast_all_through_a_cube() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let hits = tree.raycast_all(Vec3 { x: 0.1, y: -0.2, z: -4. }, Vec3::UNIT_Z);
+
+        assert_eq!(hits.len(), 2);
+        assert!((hits[0].t - 3.).abs() < 1e-5);
+        assert!((hits[1].t - 5.).abs() < 1e-5);
+        assert!(hits[0].plane.n.approx_eq(-Vec3::UNIT_Z, 1e-6));
+        assert!(hits[1].plane.n.approx_eq(Vec3::UNIT_Z, 1e-6));
+    }
+
+    #[test]
+    fn nearest_surface_point_projects_onto_the_near_face() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let nearest = tree.nearest_surface_point(Vec3 { x: 0.3, y: 1.4, z: -0.2 }).unwrap();
+
+        assert!(nearest.approx_eq(Vec3 { x: 0.3, y: 1., z: -0.2 }, 1e-5));
+        assert!(tree.nearest_surface_point(Vec3 { x: 2., y: 2., z: 0. }).unwrap().approx_eq(Vec3 { x: 1., y: 1., z: 0. }, 1e-5));
+        assert_eq!(from_polygons(Vec::<BSPPolygon>::new()).unwrap().nearest_surface_point(Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn bounds_of_the_demo_cube() {
+        let tree = from_polygons(cube(Vec3::ZERO, 5.)).unwrap();
+
+        assert_eq!(tree.bounds(), Some((Vec3::ONE * -5., Vec3::ONE * 5.)));
+        assert_eq!(from_polygons(Vec::<BSPPolygon>::new()).unwrap().bounds(), None);
+    }
+
+    #[test]
+    fn bounding_sphere_covers_the_cube() {
+        let center = Vec3 { x: 1., y: 2., z: 3. };
+        let tree = from_polygons(cube(center, 5.)).unwrap();
+        let (sphere_center, radius) = tree.bounding_sphere().unwrap();
+
+        assert!(radius * radius >= 75. - 1e-3);
+        assert!(tree.iter_polygons().flat_map(|p| p.vertices.iter()).all(|v| v.dist(sphere_center) <= radius + 1e-4));
+    }
+
+    #[test]
+    fn segment_against_a_cube() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let start = Vec3 { x: -3., y: 0.5, z: 0. };
+        let hit = tree.segment_hits_solid(start, Vec3 { x: 0., y: 0.5, z: 0. }).unwrap();
+
+        assert!((hit.t - 2. / 3.).abs() < 1e-5);
+        assert!(hit.point.approx_eq(Vec3 { x: -1., y: 0.5, z: 0. }, 1e-5));
+        assert!(tree.segment_hits_solid(start, Vec3 { x: -1.5, y: 0.5, z: 0. }).is_none());
+    }
+
+    #[test]
+    fn line_of_sight_around_a_cube() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+
+        assert!(!tree.line_of_sight(Vec3 { x: -3., y: 0., z: 0. }, Vec3 { x: 3., y: 0., z: 0. }));
+        assert!(tree.line_of_sight(Vec3 { x: -3., y: 2., z: 0. }, Vec3 { x: 3., y: 2., z: 0. }));
+        assert!(tree.line_of_sight(Vec3 { x: -3., y: 0., z: 0. }, Vec3 { x: -1.5, y: 0., z: 0. }));
+    }
+
+    #[test]
+    fn signed_distance_inside_and_outside() {
+        let tree = from_polygons(cube(Vec3::ZERO, 2.)).unwrap();
+
+        assert!((tree.signed_distance_to_surface(Vec3::ZERO) + 2.).abs() < 1e-5);
+        assert!((tree.signed_distance_to_surface(Vec3 { x: 10., y: 0., z: 0. }) - 8.).abs() < 1e-5);
+        assert!(tree.signed_distance_to_surface(Vec3 { x: 1.5, y: 0., z: 0. }) < 0.);
+    }
+
+    #[test]
+    fn volume_of_the_demo_cube() {
+        let tree = from_polygons(cube(Vec3 { x: 3., y: -1., z: 2. }, 5.)).unwrap();
+
+        assert!((tree.volume() - 1000.).abs() < 1e-2);
+    }
+
+    #[test]
+    fn surface_area_of_the_demo_cube() {
+        let tree = from_polygons(cube(Vec3::ZERO, 5.)).unwrap();
+
+        assert!((tree.surface_area() - 600.).abs() < 1e-3);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let json = serde_json::to_string(&tree).unwrap();
+        let back: BSPNode = serde_json::from_str(&json).unwrap();
+
+        assert!(json.starts_with("{\"nodes\":[{\"node\":"));
+        assert!(same_tree(&tree, &back));
+        assert_eq!(back.all_polygons().len(), 6);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_of_deep_trees() {
+        let sphere = from_polygons(bsp_sphere_faces(Vec3::ZERO, 1., 16, 8).unwrap()).unwrap();
+        let deep = chain(5_000);
+
+        for tree in [sphere, deep] {
+            let back: BSPNode = serde_json::from_str(&serde_json::to_string(&tree).unwrap()).unwrap();
+
+            assert!(tree.stats().max_depth >= 128);
+            assert!(same_tree(&tree, &back));
+        }
+
+        let inner = match chain(200) {
+            BSPNode::Node(inner) => inner,
+            BSPNode::Leaf(..) => unreachable!(),
+        };
+        let back: InnerBSPNode = serde_json::from_str(&serde_json::to_string(&inner).unwrap()).unwrap();
+
+        assert!(same_tree(&BSPNode::Node(inner), &BSPNode::Node(back)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_broken_node_lists() {
+        let plane = "{\"n\":[0.0,0.0,1.0],\"d\":0.0}";
+        let leaf = "{\"leaf\":[\"inside\",[]]}";
+        let parse = |nodes: &str| serde_json::from_str::<BSPNode>(&format!("{{\"nodes\":[{}]}}", nodes));
+
+        assert!(parse(&format!("{{\"node\":[{},[],1,2]}},{},{}", plane, leaf, leaf)).is_ok());
+        assert!(parse("").is_err());
+        assert!(parse(&format!("{{\"node\":[{},[],0,1]}},{}", plane, leaf)).is_err());
+        assert!(parse(&format!("{{\"node\":[{},[],1,1]}},{}", plane, leaf)).is_err());
+        assert!(parse(&format!("{{\"node\":[{},[],1,3]}},{},{}", plane, leaf, leaf)).is_err());
+        assert!(parse(&format!("{{\"node\":[{},[],1,2]}},{},{},{}", plane, leaf, leaf, leaf)).is_err());
+        assert!(serde_json::from_str::<InnerBSPNode>(&format!("{{\"nodes\":[{}]}}", leaf)).is_err());
+    }
+
+    #[test]
+    fn dot_declares_every_node_of_a_cube() {
+        let dot = from_polygons(cube(Vec3::ZERO, 1.)).unwrap().to_dot();
+        let declarations: Vec<&str> = dot.lines().filter(|line| line.contains(" [") && !line.contains("->")).collect();
+
+        assert!(dot.starts_with("digraph bsp {\n") && dot.ends_with("}\n"));
+        assert_eq!(declarations.len(), 13);
+        assert_eq!(declarations.iter().filter(|line| line.contains("shape=box")).count(), 7);
+        assert_eq!(dot.lines().filter(|line| line.contains("->")).count(), 12);
+    }
+
+    #[test]
+    fn split_fragments_keep_their_material() {
+        let mut faces: Vec<BSPPolygon<Vec3, u32>> = cube(Vec3::ZERO, 1.).into_iter().map(|p| p.with_data(0)).collect();
+        let splitter = cube(Vec3 { x: 0., y: 0., z: -1.5 }, 2.).remove(5).with_data(0);
+        faces[0].data = 7;
+        faces.insert(0, splitter);
+
+        let tree = from_polygons(faces).unwrap();
+        let tagged: Vec<&BSPPolygon<Vec3, u32>> = tree.iter_polygons().filter(|p| p.plane.n.approx_eq(-Vec3::UNIT_X, 1e-6)).collect();
+
+        assert_eq!(tagged.len(), 2);
+        assert!(tagged.iter().all(|p| p.data == 7));
+    }
+
+    #[test]
+    fn borrowed_payloads_build() {
+        let names: Vec<String> = (0..6).map(|i| format!("face {}", i)).collect();
+        let faces: Vec<BSPPolygon<Vec3, &str>> = cube(Vec3::ZERO, 1.).into_iter().zip(names.iter()).map(|(p, name)| p.with_data(name.as_str())).collect();
+        let tree = from_polygons(faces).unwrap();
+
+        assert_eq!(tree.iter_polygons().filter(|p| p.data.starts_with("face ")).count(), 6);
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    #[test]
+    fn shared_payloads_build_without_rayon() {
+        use alloc::rc::Rc;
+
+        struct Material {
+            name: &'static str,
+        }
+
+        let steel = Rc::new(Material { name: "steel" });
+        let mut faces: Vec<BSPPolygon<Vec3, Rc<Material>>> = cube(Vec3::ZERO, 1.).into_iter().map(|p| p.with_data(steel.clone())).collect();
+        faces.insert(0, cube(Vec3 { x: 0., y: 0., z: -1.5 }, 2.).remove(5).with_data(steel.clone()));
+
+        let tree = from_polygons(faces).unwrap();
+
+        assert_eq!(tree.iter_polygons().count(), 11);
+        assert!(tree.iter_polygons().all(|p| Rc::ptr_eq(&p.data, &steel) && p.data.name == "steel"));
+    }
+
+    #[test]
+    fn translated_cube_moves_its_interior() {
+        let mut tree = from_polygons(cube(Vec3::ZERO, 1.)).unwrap();
+        let p = Vec3 { x: 0.5, y: 0.25, z: -0.25 };
+        let offset = Vec3 { x: 10., y: 0., z: 0. };
+
+        assert!(tree.point_in_solid(p));
+        tree.transform(&Mat4::translation(offset));
+
+        assert!(!tree.point_in_solid(p));
+        assert!(tree.point_in_solid(p + offset));
+    }
+
+    #[test]
+    fn mirrored_cube_stays_outward_facing() {
+        let mut tree = from_polygons(cube(Vec3 { x: 2., y: 0., z: 0. }, 1.)).unwrap();
+        tree.mirror_across(BSPPlane { n: Vec3::UNIT_X, d: 0. });
+        let center = Vec3 { x: -2., y: 0., z: 0. };
+
+        assert!((tree.volume() - 8.).abs() < 1e-4);
+        assert!(tree.point_in_solid(center));
+        assert!(!tree.point_in_solid(Vec3 { x: 2., y: 0., z: 0. }));
+        assert!(tree.iter_polygons().all(|p| p.plane.n.dot(p.centroid() - center) > 0.));
+    }
+
+    #[test]
+    fn strategies_see_the_build_tolerance() {
+        struct ExpectEps(Scalar, bool);
+
+        impl SplitStrategy for ExpectEps {
+            fn choose(&self, polygons: &[BSPPolygon], config: &BuildConfig) -> usize {
+                assert_eq!((config.eps, config.relative_eps), (self.0, self.1));
+                return best_scoring_splitter(polygons, config, 1.);
+            }
+        }
+
+        let faces = cube(Vec3::ZERO, 1.);
+        let strategy = ExpectEps(1e-3, true);
+        let tree = BSPTreeBuilder::new().epsilon(1e-3).relative_epsilon(true).strategy(&strategy).build(faces).unwrap();
+
+        assert_eq!(tree.stats().node_count, 6);
+    }
+
+    #[test]
+    fn builder_counts_the_polygons_it_splits() {
+        let mut faces = cube(Vec3::ZERO, 1.);
+        faces.insert(0, cube(Vec3 { x: 0., y: 0., z: -1.5 }, 2.).remove(5));
+
+        let (tree, stats) = BSPTreeBuilder::new().build_with_stats(faces).unwrap();
+
+        assert_eq!(stats.split_count, 4);
+        assert_eq!(stats, TreeStats { split_count: 4, ..tree.stats() });
+        assert_eq!(tree.stats().split_count, 0);
+        assert_eq!(BSPTreeBuilder::new().build_with_stats(cube(Vec3::ZERO, 1.)).unwrap().1.split_count, 0);
+    }
+}